@@ -1,6 +1,8 @@
 use bevy::prelude::*;
 use bevy_kira_audio::AudioInstance;
 
+use crate::events::VoiceKind;
+
 /// Speaki entity marker
 #[derive(Component)]
 pub struct Speaki;
@@ -84,6 +86,14 @@ impl Default for IdleVoiceTimer {
 #[derive(Component)]
 pub struct Dragged;
 
+/// Marker for speakis in the current selection
+#[derive(Component)]
+pub struct Selected;
+
+/// Marker for speakis pinned in place (no gravity or movement unless dragged)
+#[derive(Component)]
+pub struct Pinned;
+
 /// Speaki size
 #[derive(Component)]
 pub struct SpeakiSize(pub f32);
@@ -94,14 +104,53 @@ impl Default for SpeakiSize {
     }
 }
 
+impl SpeakiSize {
+    /// Human readable size class (for tooltips)
+    pub fn tier(&self) -> &'static str {
+        match self.0 {
+            s if s < 100.0 => "Tiny",
+            s if s < 200.0 => "Normal",
+            s if s < 300.0 => "Big",
+            s if s < 450.0 => "Huge",
+            _ => "Giant",
+        }
+    }
+}
+
+/// Display name of a speaki
+#[derive(Component)]
+pub struct SpeakiName(pub String);
+
+impl SpeakiName {
+    const NAMES: [&'static str; 10] = [
+        "스피키", "Speaki", "Piki", "Kiki", "Mochi", "Dango", "Pudding", "Tofu", "Bun", "Momo",
+    ];
+
+    pub fn random() -> Self {
+        let name = Self::NAMES[rand::random_range(0..Self::NAMES.len())];
+        Self(name.to_string())
+    }
+}
+
 /// Current audio being played by this speaki
 #[derive(Component, Default)]
 pub struct CurrentAudio {
     pub handle: Option<Handle<AudioInstance>>,
+    pub voice_index: Option<usize>,
+    pub volume: f32,             // Event volume, before master volume and attenuation
+    pub kind: Option<VoiceKind>, // What the playing voice is reacting to
+    pub started: f32,            // Time the last voice started (for the per-speaki cooldown)
 }
 
-/// Shiny speaki marker - rare glowing variant
+/// Speech bubble caption for the voice a speaki is playing
 #[derive(Component)]
+pub struct Caption {
+    pub voice: usize,
+    pub ended: Option<f32>, // Time the voice ended (the caption is fading out)
+}
+
+/// Shiny speaki marker - rare glowing variant
+#[derive(Component, Clone)]
 pub struct Shiny {
     pub base_color: Color,
     pub pulse_phase: f32,        // Random phase offset for each shiny
@@ -126,3 +175,25 @@ pub struct Shockwave {
     pub max_radius: f32,   // Maximum size to reach
     pub color: Color,      // Base color
 }
+
+/// Network id shared by host and clients (LAN multiplayer)
+#[derive(Component, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NetId(pub u32);
+
+/// Identity kept across undo/redo (a restored speaki is a new entity)
+#[derive(Component, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct UndoId(pub u64);
+
+/// Speaki held by a remote user (LAN multiplayer, host side)
+#[derive(Component)]
+pub struct HeldBy(pub u32);
+
+/// Marker for the gamepad's virtual cursor
+#[derive(Component)]
+pub struct GamepadCursorMarker;
+
+/// Cursor marker of a remote user
+#[derive(Component)]
+pub struct RemoteCursor {
+    pub user: u32,
+}
@@ -1,5 +1,7 @@
 use bevy::prelude::*;
 
+use crate::resources::PortalEdge;
+
 /// Event to spawn a new speaki
 #[derive(Message)]
 pub struct SpawnSpeakiEvent {
@@ -19,12 +21,53 @@ pub struct PlayVoiceEvent {
     pub entity: Option<Entity>, // The speaki entity playing this voice (for mouth animation)
     pub voice_index: usize,
     pub volume: f32,
+    pub kind: VoiceKind,
+}
+
+/// What a voice is reacting to, which decides who wins when voices compete
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum VoiceKind {
+    Grab,
+    Create,
+    Remove,
+    Bounce,
+    Collision,
+    Idle,
+    Manual, // Asked for directly (context menu, control API)
+}
+
+impl VoiceKind {
+    /// Higher priority voices replace lower ones when the voice limit is reached
+    pub fn priority(self) -> u8 {
+        match self {
+            VoiceKind::Grab | VoiceKind::Manual => 3,
+            VoiceKind::Create | VoiceKind::Remove => 2,
+            VoiceKind::Bounce | VoiceKind::Collision => 1,
+            VoiceKind::Idle => 0,
+        }
+    }
+
+    /// Impact sounds play on the effects channel, everything else on the voice channel
+    pub fn is_effect(self) -> bool {
+        matches!(self, VoiceKind::Bounce | VoiceKind::Collision)
+    }
 }
 
 /// Event for wall bounce (to trigger sound)
 #[derive(Message)]
 pub struct WallBounceEvent {
     pub entity: Entity,
+    pub speed: f32,   // Speed into the wall (pixels per frame)
+    pub normal: Vec2, // Wall normal, pointing into the box
+}
+
+/// Event for two speakis bumping into each other (to trigger sound)
+#[derive(Message)]
+pub struct SpeakiCollisionEvent {
+    pub entity1: Entity,
+    pub entity2: Entity,
+    pub speed: f32,   // Closing speed along the normal (pixels per frame)
+    pub normal: Vec2, // From entity1 towards entity2
 }
 
 /// Event for merging two speakis (Suika game style)
@@ -32,7 +75,17 @@ pub struct WallBounceEvent {
 pub struct MergeSpeakiEvent {
     pub entity1: Entity,
     pub entity2: Entity,
-    pub position: Vec2,
-    pub combined_velocity: Vec2,
-    pub new_size: f32,
+}
+
+/// Event for a speaki that fully left the box through an open (portal) edge
+#[derive(Message)]
+pub struct PortalExitEvent {
+    pub entity: Entity,
+    pub edge: PortalEdge,
+}
+
+/// Event for a shiny speaki exploding
+#[derive(Message)]
+pub struct ShinyExplosionEvent {
+    pub entity: Entity,
 }
@@ -7,7 +7,11 @@ mod resources;
 mod systems;
 
 use bevy::prelude::*;
-use bevy_egui::{EguiPlugin, EguiPrimaryContextPass, input::egui_wants_any_pointer_input};
+use bevy::sprite::{SpritePickingMode, SpritePickingSettings};
+use bevy_egui::{
+    EguiPlugin, EguiPrimaryContextPass,
+    input::{egui_wants_any_keyboard_input, egui_wants_any_pointer_input},
+};
 use bevy_embedded_assets::{EmbeddedAssetPlugin, PluginMode};
 use bevy_kira_audio::prelude::*;
 
@@ -16,10 +20,12 @@ use resources::*;
 use systems::*;
 
 fn main() {
+    #[cfg(not(target_arch = "wasm32"))]
+    let args: Vec<String> = std::env::args().collect();
+
     // Check for --transparent flag or SPEAKI_TRANSPARENT env var (native only)
     #[cfg(not(target_arch = "wasm32"))]
     let transparent = {
-        let args: Vec<String> = std::env::args().collect();
         let t = args.contains(&"--transparent".to_string())
             || std::env::var("SPEAKI_TRANSPARENT")
                 .map(|v| v == "1" || v.to_lowercase() == "true")
@@ -31,6 +37,10 @@ fn main() {
     #[cfg(target_arch = "wasm32")]
     let transparent = false;
 
+    // Check for --host [port] / --join <address> (LAN multiplayer, native only)
+    #[cfg(not(target_arch = "wasm32"))]
+    let net_args = NetArgs::from_args(&args);
+
     // Set initial config based on transparent mode
     let mut game_config = GameConfig::default();
     if transparent {
@@ -52,6 +62,15 @@ fn main() {
 
     let mut app = App::new();
 
+    // The user's music folder, registered before the asset plugin sets up its sources
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some(dir) = music_dir() {
+        app.register_asset_source(
+            "music",
+            bevy::asset::io::AssetSourceBuilder::platform_default(&dir.to_string_lossy(), None),
+        );
+    }
+
     app.insert_resource(clear_color)
         .add_plugins(EmbeddedAssetPlugin {
             mode: PluginMode::ReplaceDefault,
@@ -67,32 +86,60 @@ fn main() {
             }),
             ..default()
         }))
+        // Pixel-accurate sprite picking: ignore (nearly) transparent pixels
+        .insert_resource(SpritePickingSettings {
+            require_markers: false,
+            picking_mode: SpritePickingMode::AlphaThreshold(0.5),
+        })
         .add_plugins(AudioPlugin)
+        .add_audio_channel::<VoiceChannel>()
+        .add_audio_channel::<EffectsChannel>()
+        .add_audio_channel::<MusicChannel>()
         .add_plugins(EguiPlugin::default())
         // Resources
         .insert_resource(game_config)
         .init_resource::<PhysicsConfig>()
-        .init_resource::<AudioConfig>()
+        .insert_resource(AudioConfig::load())
+        .init_resource::<VoiceManager>()
+        .init_resource::<MusicPlayer>()
+        .init_resource::<CaptionTable>()
         .init_resource::<BorderConfig>()
         .init_resource::<DragState>()
+        .init_resource::<TouchState>()
+        .init_resource::<SelectionBox>()
+        .init_resource::<ContextMenu>()
+        .init_resource::<Inspector>()
+        .init_resource::<ZOrder>()
         .init_resource::<SettingsOpen>()
         .init_resource::<FontLoaded>()
         .init_resource::<SpriteAssets>()
         .init_resource::<AudioAssets>()
         .init_resource::<ImageGroups>()
-        .init_resource::<VoiceGroups>()
+        .insert_resource(VoiceGroups::load())
+        .init_resource::<VoiceEditor>()
         .init_resource::<WindowPositionTracker>()
         .init_resource::<ShinyConfig>()
         .init_resource::<MergeConfig>()
+        .init_resource::<NetMode>()
+        .init_resource::<GamepadConfig>()
+        .init_resource::<GamepadCursor>()
+        .init_resource::<UndoHistory>()
+        .init_resource::<SimulationSpeed>()
+        .insert_resource(KeyBindings::load())
         // Events
         .add_message::<SpawnSpeakiEvent>()
         .add_message::<DespawnSpeakiEvent>()
         .add_message::<PlayVoiceEvent>()
         .add_message::<WallBounceEvent>()
+        .add_message::<SpeakiCollisionEvent>()
         .add_message::<MergeSpeakiEvent>()
+        .add_message::<PortalExitEvent>()
+        .add_message::<ShinyExplosionEvent>()
         // Startup
         .add_systems(Startup, (setup_camera, load_assets))
         .add_systems(Startup, spawn_initial_speakis.after(load_assets))
+        .add_systems(Startup, setup_gamepad_cursor)
+        .add_systems(Startup, setup_music_system)
         // Input systems
         .add_systems(
             Update,
@@ -100,16 +147,71 @@ fn main() {
                 mouse_input_system,
                 drag_update_system,
                 drag_release_system,
-                right_click_delete_system,
+                selection_box_system,
+                right_click_system,
             )
                 .chain()
-                .run_if(not(egui_wants_any_pointer_input)),
+                .run_if(not(egui_wants_any_pointer_input))
+                .run_if(not(resource_equals(NetMode::Client))),
+        )
+        // Selection shortcuts (delete, resize, select all)
+        .add_systems(
+            Update,
+            selection_keyboard_system
+                .run_if(not(egui_wants_any_keyboard_input))
+                .run_if(not(resource_equals(NetMode::Client))),
+        )
+        // Rebindable hotkeys (Settings → Controls)
+        .add_systems(
+            Update,
+            (
+                hotkey_toggle_system,
+                hotkey_speaki_system.run_if(not(resource_equals(NetMode::Client))),
+            )
+                .run_if(not(egui_wants_any_keyboard_input)),
         )
+        .add_systems(
+            Update,
+            rebind_capture_system
+                .after(hotkey_toggle_system)
+                .after(hotkey_speaki_system),
+        )
+        // Undo/redo (Ctrl+Z / Ctrl+Shift+Z by default)
+        .add_systems(
+            Update,
+            undo_keyboard_system
+                .run_if(not(egui_wants_any_keyboard_input))
+                .run_if(not(resource_equals(NetMode::Client))),
+        )
+        .add_systems(
+            PostUpdate,
+            undo_record_system.run_if(not(resource_equals(NetMode::Client))),
+        )
+        // Touch input: one drag per finger, pinch to resize, long press to remove
+        .add_systems(
+            Update,
+            (touch_press_system, touch_drag_system, touch_release_system)
+                .chain()
+                .run_if(not(egui_wants_any_pointer_input))
+                .run_if(not(resource_equals(NetMode::Client))),
+        )
+        // Gamepad: virtual grab cursor, gravity tilt and rumble
+        .add_systems(
+            Update,
+            (gamepad_cursor_system, gamepad_tilt_system)
+                .run_if(not(resource_equals(NetMode::Client))),
+        )
+        .add_systems(Update, gamepad_rumble_system)
         // Spawn/Despawn
-        .add_systems(Update, (spawn_speaki_system, despawn_speaki_system))
-        // Physics systems
         .add_systems(
             Update,
+            (spawn_speaki_system, despawn_speaki_system)
+                .run_if(not(resource_equals(NetMode::Client))),
+        )
+        .add_systems(Update, z_order_system)
+        // Physics systems (one step each; pause / slow motion / fast forward decide how many run)
+        .add_systems(
+            SimulationStep,
             (
                 gravity_system,
                 movement_system,
@@ -119,10 +221,19 @@ fn main() {
                 rotation_system,
                 window_inertia_system,
                 shiny_explosion_system,
-                shockwave_animation_system,
             )
                 .chain(),
         )
+        .add_systems(
+            Update,
+            (
+                held_movement_system,
+                simulation_step_system,
+                shockwave_animation_system,
+            )
+                .chain()
+                .run_if(not(resource_equals(NetMode::Client))),
+        )
         // Animation systems
         .add_systems(
             Update,
@@ -132,6 +243,8 @@ fn main() {
                 change_to_sad_system,
                 change_to_normal_system,
                 shiny_glow_system,
+                hover_highlight_system,
+                selection_gizmo_system,
             ),
         )
         // Audio systems
@@ -139,32 +252,100 @@ fn main() {
             Update,
             (
                 play_voice_system,
+                voice_spatial_system.after(play_voice_system),
+                channel_volume_system,
+                save_mute_system,
+                music_player_system,
+                voice_envelope_system,
                 bounce_voice_system,
+                collision_voice_system,
                 merge_voice_system,
+                explosion_voice_system,
+                thrown_voice_system,
+                wake_up_voice_system,
                 idle_voice_system,
                 mouth_animation_system,
+                caption_system.after(mouth_animation_system),
             ),
         )
         // UI systems
         .add_systems(
             Update,
             (
-                toggle_settings_system,
-                toggle_titlebar_system,
                 sync_background_color_system,
                 sync_window_settings_system,
                 sync_bloom_system,
             ),
         );
 
+    // Optional: local control server for driving the box from other programs
+    #[cfg(all(feature = "control", not(target_arch = "wasm32")))]
+    app.add_systems(Startup, start_control_server)
+        .add_systems(Update, control_command_system);
+
+    // Optional: speakis react to the microphone
+    #[cfg(all(feature = "mic", not(target_arch = "wasm32")))]
+    app.add_systems(Startup, start_mic_system).add_systems(
+        Update,
+        mic_reaction_system.run_if(resource_exists::<MicInput>),
+    );
+
     // Native-only: window drag with Alt+Click
     #[cfg(not(target_arch = "wasm32"))]
     app.add_systems(Update, window_drag_system);
 
+    // Native-only: LAN shared box (host simulates, clients mirror)
+    #[cfg(not(target_arch = "wasm32"))]
+    app.insert_resource(net_args.mode)
+        .insert_resource(net_args)
+        .add_systems(Startup, setup_net_session)
+        .add_systems(
+            Update,
+            (
+                net_assign_ids_system,
+                host_receive_system,
+                host_remote_drag_system,
+                host_broadcast_system,
+                host_cursor_marker_system,
+            )
+                .chain()
+                .run_if(resource_exists::<NetHost>),
+        )
+        .add_systems(
+            Update,
+            (
+                client_receive_system,
+                client_input_system.run_if(not(egui_wants_any_pointer_input)),
+                client_cursor_marker_system,
+            )
+                .chain()
+                .run_if(resource_exists::<NetClient>),
+        );
+
+    // Native-only: portal edges linking neighbouring instances
+    #[cfg(not(target_arch = "wasm32"))]
+    app.add_systems(Startup, setup_portal_link).add_systems(
+        Update,
+        (
+            portal_link_system,
+            portal_exit_system.after(simulation_step_system),
+        ),
+    );
+
     app
         .add_systems(
             EguiPrimaryContextPass,
-            (setup_fonts_system, settings_ui_system).chain(),
+            (
+                setup_fonts_system,
+                settings_ui_system,
+                hover_feedback_ui_system,
+                context_menu_ui_system,
+                inspector_ui_system,
+                voice_editor_ui_system,
+                simulation_hud_ui_system,
+                caption_ui_system,
+            )
+                .chain(),
         );
 
     // Native-only: set window icon
@@ -212,6 +393,7 @@ fn set_window_icon(_marker: bevy::ecs::system::NonSendMarker) {
 fn load_assets(
     mut sprites: ResMut<SpriteAssets>,
     mut audio: ResMut<AudioAssets>,
+    voice_groups: Res<VoiceGroups>,
     asset_server: Res<AssetServer>,
 ) {
     // Load sprite images
@@ -293,31 +475,12 @@ fn load_assets(
 
     sprites.loaded = true;
 
-    // Load audio files
-    let voice_files = [
-        "voice/dontpress.mp3",  // 0 - drag
-        "voice/tryhard.mp3",    // 1 - drag
-        "voice/speakifull.mp3", // 2 - drag
-        "voice/speakif.mp3",    // 3 - drag
-        "voice/speaki.mp3",     // 4 - create
-        "voice/g1.mp3",         // 5 - idle
-        "voice/g2.mp3",         // 6 - idle
-        "voice/g3.mp3",         // 7 - idle
-        "voice/gs1.mp3",        // 8 - idle
-        "voice/gs2.mp3",        // 9 - idle
-        "voice/gs3.mp3",        // 10 - idle
-        "voice/gs4.mp3",        // 11 - idle
-        "voice/sc1.mp3",        // 12 - idle2
-        "voice/sc1e.mp3",       // 13
-        "voice/sc2.mp3",        // 14 - idle2
-        "voice/sc2s.mp3",       // 15 - remove
-        "voice/sc2e.mp3",       // 16 - bounce
-    ];
-
-    for file in &voice_files {
-        let handle: Handle<bevy_kira_audio::AudioSource> = asset_server.load(*file);
+    // Load audio files listed in the voice manifest
+    for file in &voice_groups.files {
+        let handle: Handle<bevy_kira_audio::AudioSource> = asset_server.load(file.clone());
         audio.voices.push(handle);
     }
+    audio.names = voice_groups.names.clone();
 
     audio.loaded = true;
 }
@@ -1,5 +1,8 @@
 use bevy::prelude::*;
-use bevy_kira_audio::AudioSource;
+use bevy_kira_audio::{AudioInstance, AudioSource, PlaybackState};
+use serde::{Deserialize, Serialize};
+
+use crate::events::VoiceKind;
 
 /// Game configuration
 #[derive(Resource)]
@@ -8,6 +11,9 @@ pub struct GameConfig {
     pub speaki_size: f32,
     pub click_to_add: bool,
     pub eye_blink_enabled: bool,
+    pub hover_highlight: bool,
+    pub hover_tooltip: bool,
+    pub instant_delete: bool, // Right-click deletes right away instead of opening the menu
     pub background_color: [f32; 3],
     pub background_alpha: f32,
     pub window_transparent: bool,
@@ -22,6 +28,9 @@ impl Default for GameConfig {
             speaki_size: 150.0,
             click_to_add: true,
             eye_blink_enabled: true,
+            hover_highlight: true,
+            hover_tooltip: false,
+            instant_delete: false,
             background_color: [0.1, 0.1, 0.1],
             background_alpha: 1.0,
             window_transparent: false,
@@ -35,6 +44,7 @@ impl Default for GameConfig {
 #[derive(Resource)]
 pub struct PhysicsConfig {
     pub gravity: f32,
+    pub gravity_direction: Vec2, // Unit vector, tilted by the gamepad
     pub bounce: f32,
     pub friction: f32,
     pub rotation_speed: f32,
@@ -42,6 +52,11 @@ pub struct PhysicsConfig {
     pub collision_damping: f32,
     pub cursor_impulse: f32,
     pub cursor_throwing_power: f32,
+    pub max_throw_speed: f32, // Cap on throw velocity (pixels per frame)
+    pub throw_spin: f32,      // Spin per radian of drag curvature
+    pub spring_drag: bool,    // Pull held speakis toward the cursor instead of teleporting them
+    pub drag_stiffness: f32,  // Spring strength of spring drag
+    pub drag_damping: f32,    // Velocity kept per frame while spring dragging
     pub bounce_responsiveness: f32,
 }
 
@@ -49,6 +64,7 @@ impl Default for PhysicsConfig {
     fn default() -> Self {
         Self {
             gravity: 0.5,
+            gravity_direction: Vec2::NEG_Y,
             bounce: 0.7,
             friction: 0.5,
             rotation_speed: 0.3,
@@ -56,33 +72,233 @@ impl Default for PhysicsConfig {
             collision_damping: 0.99,
             cursor_impulse: 20.0,
             cursor_throwing_power: 1.0,
+            max_throw_speed: 80.0,
+            throw_spin: 0.4,
+            spring_drag: false,
+            drag_stiffness: 0.2,
+            drag_damping: 0.7,
             bounce_responsiveness: 1.0,
         }
     }
 }
 
+impl PhysicsConfig {
+    /// Final throw velocity and spin for a finished drag
+    pub fn throw(&self, history: &DragHistory, now: f32) -> (Vec2, f32) {
+        self.throw_from(history.velocity(now), history.curvature(now))
+    }
+
+    /// Apply throwing power, speed cap and spin scale to a raw drag estimate
+    pub fn throw_from(&self, velocity: Vec2, curvature: f32) -> (Vec2, f32) {
+        let velocity =
+            (velocity * self.cursor_throwing_power).clamp_length_max(self.max_throw_speed);
+        let spin = (curvature * self.throw_spin).clamp(-1.0, 1.0);
+        (velocity, spin)
+    }
+}
+
+/// Recent pointer samples of a drag, used to estimate throw velocity and spin
+#[derive(Default, Clone)]
+pub struct DragHistory {
+    samples: std::collections::VecDeque<(f32, Vec2)>, // (time, position)
+}
+
+impl DragHistory {
+    pub const CAPACITY: usize = 32;
+    pub const WINDOW: f32 = 0.1; // Seconds of history used for the estimate
+    pub const TIME_SCALE: f32 = 0.05; // Velocity is measured per 50ms, as throws always were
+
+    /// Start a new drag at the given position
+    pub fn start(&mut self, time: f32, position: Vec2) {
+        self.samples.clear();
+        self.samples.push_back((time, position));
+    }
+
+    /// Record the pointer position (once per frame while dragging)
+    pub fn push(&mut self, time: f32, position: Vec2) {
+        if let Some(last) = self.samples.back_mut()
+            && last.0 >= time
+        {
+            // Same frame: keep the newest position only
+            last.1 = position;
+            return;
+        }
+
+        if self.samples.len() == Self::CAPACITY {
+            self.samples.pop_front();
+        }
+        self.samples.push_back((time, position));
+    }
+
+    fn recent(&self, now: f32) -> impl Iterator<Item = &(f32, Vec2)> {
+        self.samples
+            .iter()
+            .filter(move |(t, _)| now - t <= Self::WINDOW)
+    }
+
+    /// Least-squares slope of position over time in the recent window
+    pub fn velocity(&self, now: f32) -> Vec2 {
+        let count = self.recent(now).count();
+        if count < 2 {
+            return Vec2::ZERO;
+        }
+
+        let n = count as f32;
+        let mean_t = self.recent(now).map(|(t, _)| t).sum::<f32>() / n;
+        let mean_p = self.recent(now).map(|(_, p)| *p).sum::<Vec2>() / n;
+
+        let mut cov = Vec2::ZERO;
+        let mut var = 0.0;
+        for (t, p) in self.recent(now) {
+            let dt = t - mean_t;
+            cov += (*p - mean_p) * dt;
+            var += dt * dt;
+        }
+
+        if var <= f32::EPSILON {
+            return Vec2::ZERO;
+        }
+
+        cov / var * Self::TIME_SCALE
+    }
+
+    /// Signed turning angle of the recent path (positive = counterclockwise)
+    pub fn curvature(&self, now: f32) -> f32 {
+        let points: Vec<Vec2> = self.recent(now).map(|(_, p)| *p).collect();
+
+        let mut turning = 0.0;
+        let mut last_segment: Option<Vec2> = None;
+        for pair in points.windows(2) {
+            let segment = pair[1] - pair[0];
+            // Ignore jitter
+            if segment.length_squared() < 1.0 {
+                continue;
+            }
+            if let Some(last) = last_segment {
+                turning += last.angle_to(segment);
+            }
+            last_segment = Some(segment);
+        }
+
+        turning
+    }
+}
+
 /// Audio configuration
 #[derive(Resource)]
 pub struct AudioConfig {
     pub master_volume: f32,
+    pub muted: bool, // Global mute, remembered between runs
+    pub voice_volume: f32,
+    pub voice_muted: bool,
+    pub effects_volume: f32,
+    pub effects_muted: bool,
+    pub music_volume: f32,
+    pub music_muted: bool,
+    pub music_enabled: bool,
+    pub music_crossfade: f32,      // Seconds of overlap between tracks
+    pub music_focus_pause: bool,   // Pause music while the window is in the background
     pub grab_volume: f32,
     pub bounce_volume: f32,
+    pub collision_volume: f32,
     pub create_volume: f32,
     pub remove_volume: f32,
     pub idle_volume: f32,
     pub idle_frequency: f32,
+    pub stereo_panning: bool,      // Pan voices by the speaki's x position in the box
+    pub pan_width: f32,            // How far to the sides voices at the box edges go (0-1)
+    pub cursor_attenuation: bool,  // Speakis far from the cursor sound quieter
+    pub attenuation_distance: f32, // Distance (pixels) at which attenuation is strongest
+    pub size_pitch: bool,          // Big speakis sound deeper, small ones squeakier
+    pub min_pitch: f32,            // Playback rate for the biggest speakis
+    pub max_pitch: f32,            // Playback rate for the smallest speakis
+    pub pitch_variation: f32,      // Random pitch change per voice (fraction, e.g. 0.05 = ±5%)
+    pub impact_volume: bool,       // Scale bounce and collision volume with impact speed
+    pub hard_impact_speed: f32,    // Impact speed (px/frame) for full volume and the hard voice
+    pub throw_voice_speed: f32,    // Release speed (px/frame) for the thrown-far voice
+    pub wake_up_time: f32,         // Seconds lying still before a knock plays the wake-up voice
+    pub max_voices: usize,         // Voices allowed to play at once
+    pub voice_cooldown: f32,       // Seconds a speaki's voice is safe from one of equal priority
+    pub group_cooldown: f32,       // Seconds between two voices of one group (grabs excepted)
+    pub lip_sync: bool,            // Flap the mouth with the voice's loudness
+    pub lip_sync_threshold: f32,   // Loudness (0-1 of the clip's peak) that opens the mouth
+    pub captions: bool,            // Speech bubbles with the voice line above the speaki
+    pub caption_language: CaptionLanguage,
+    pub ducking: bool,             // Quiet idle chatter while a grab or remove voice plays
+    pub duck_level: f32,           // Idle chatter volume while ducked (0-1)
 }
 
 impl Default for AudioConfig {
     fn default() -> Self {
         Self {
             master_volume: 0.3,
+            muted: false,
+            voice_volume: 1.0,
+            voice_muted: false,
+            effects_volume: 1.0,
+            effects_muted: false,
+            music_volume: 0.5,
+            music_muted: false,
+            music_enabled: false,
+            music_crossfade: 3.0,
+            music_focus_pause: true,
             grab_volume: 1.0,
             bounce_volume: 0.3,
+            collision_volume: 0.2,
             create_volume: 1.0,
             remove_volume: 1.0,
             idle_volume: 0.8,
             idle_frequency: 0.5,
+            stereo_panning: true,
+            pan_width: 0.8,
+            cursor_attenuation: false,
+            attenuation_distance: 1200.0,
+            size_pitch: true,
+            min_pitch: 0.6,
+            max_pitch: 1.5,
+            pitch_variation: 0.05,
+            impact_volume: true,
+            hard_impact_speed: 50.0,
+            throw_voice_speed: 40.0,
+            wake_up_time: 20.0,
+            max_voices: 8,
+            voice_cooldown: 0.3,
+            group_cooldown: 0.08,
+            lip_sync: true,
+            lip_sync_threshold: 0.35,
+            captions: false,
+            caption_language: CaptionLanguage::English,
+            ducking: true,
+            duck_level: 0.3,
+        }
+    }
+}
+
+impl AudioConfig {
+    const FILE: &'static str = "audio.json";
+
+    /// Defaults with the saved mute state
+    pub fn load() -> Self {
+        let muted = read_settings_file(Self::FILE)
+            .and_then(|json| serde_json::from_str::<serde_json::Value>(&json).ok())
+            .and_then(|saved| saved.get("muted").and_then(serde_json::Value::as_bool));
+        Self {
+            muted: muted.unwrap_or(false),
+            ..default()
+        }
+    }
+
+    pub fn save(&self) {
+        let json = serde_json::json!({ "muted": self.muted });
+        write_settings_file(Self::FILE, &json.to_string());
+    }
+
+    /// Linear volume for a channel after master volume and mutes
+    pub fn channel_volume(&self, volume: f32, muted: bool) -> f32 {
+        if self.muted || muted {
+            0.0
+        } else {
+            self.master_volume * volume
         }
     }
 }
@@ -94,6 +310,11 @@ pub struct BorderConfig {
     pub right: f32,
     pub up: f32,
     pub down: f32,
+    // Portal edges: speakis pass through to a neighbouring instance instead of bouncing
+    pub portal_left: bool,
+    pub portal_right: bool,
+    pub portal_up: bool,
+    pub portal_down: bool,
 }
 
 impl Default for BorderConfig {
@@ -103,6 +324,50 @@ impl Default for BorderConfig {
             right: 0.0,
             up: 0.0,
             down: 0.0,
+            portal_left: false,
+            portal_right: false,
+            portal_up: false,
+            portal_down: false,
+        }
+    }
+}
+
+impl BorderConfig {
+    /// Box bounds in world coordinates for a window of the given size
+    pub fn bounds(&self, width: f32, height: f32) -> Rect {
+        let half_width = width / 2.0;
+        let half_height = height / 2.0;
+
+        Rect {
+            min: Vec2::new(
+                -half_width + half_width * self.left,
+                -half_height + half_height * self.down,
+            ),
+            max: Vec2::new(
+                half_width - half_width * self.right,
+                half_height - half_height * self.up,
+            ),
+        }
+    }
+}
+
+/// Box edge a speaki can leave through
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum PortalEdge {
+    Left,
+    Right,
+    Top,
+    Bottom,
+}
+
+impl PortalEdge {
+    /// The edge a speaki enters through on the other side
+    pub fn opposite(self) -> Self {
+        match self {
+            PortalEdge::Left => PortalEdge::Right,
+            PortalEdge::Right => PortalEdge::Left,
+            PortalEdge::Top => PortalEdge::Bottom,
+            PortalEdge::Bottom => PortalEdge::Top,
         }
     }
 }
@@ -112,11 +377,344 @@ impl Default for BorderConfig {
 pub struct DragState {
     pub is_dragging: bool,
     pub dragged_entity: Option<Entity>,
-    pub last_start_pos: Vec2,
-    pub last_start_time: f32,
+    pub grab_offset: Vec2,          // Grab point in the speaki's local space
+    pub group: Vec<(Entity, Vec2)>, // Other selected speakis dragged along (offset from cursor)
+    pub history: DragHistory,
     pub last_click_time: f32, // For double-click detection
 }
 
+/// Right-click menu for a speaki
+#[derive(Resource, Default)]
+pub struct ContextMenu {
+    pub target: Option<Entity>,
+    pub position: Vec2, // Screen position (logical pixels) where it opened
+}
+
+/// Language of the voice captions
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CaptionLanguage {
+    English,
+    Japanese,
+    Korean,
+}
+
+impl CaptionLanguage {
+    pub const ALL: [CaptionLanguage; 3] = [
+        CaptionLanguage::English,
+        CaptionLanguage::Japanese,
+        CaptionLanguage::Korean,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            CaptionLanguage::English => "English",
+            CaptionLanguage::Japanese => "日本語",
+            CaptionLanguage::Korean => "한국어",
+        }
+    }
+}
+
+/// Caption text per voice file, in each language (indexed by `CaptionLanguage`)
+#[derive(Resource)]
+pub struct CaptionTable {
+    pub captions: std::collections::HashMap<String, [String; 3]>,
+}
+
+impl Default for CaptionTable {
+    fn default() -> Self {
+        // voice file: English, Japanese, Korean
+        let captions = [
+            ("dontpress", "Don't press!", "押さないで！", "누르지 마!"),
+            (
+                "tryhard",
+                "I'm trying hard!",
+                "がんばってるの！",
+                "열심히 하고 있어!",
+            ),
+            ("speakifull", "Speakiii~!", "スピキ〜！", "스피키~!"),
+            ("speakif", "Speaki!", "スピキ！", "스피키!"),
+            ("speaki", "Speaki!", "スピキ！", "스피키!"),
+            ("g1", "(giggles)", "（くすくす）", "(킥킥)"),
+            ("g2", "(giggles)", "（くすくす）", "(킥킥)"),
+            ("g3", "(giggles)", "（くすくす）", "(킥킥)"),
+            ("gs1", "(mumbles)", "（もごもご）", "(웅얼웅얼)"),
+            ("gs2", "(mumbles)", "（もごもご）", "(웅얼웅얼)"),
+            ("gs3", "(mumbles)", "（もごもご）", "(웅얼웅얼)"),
+            ("gs4", "(mumbles)", "（もごもご）", "(웅얼웅얼)"),
+            ("sc1", "(squeals)", "（きゃー）", "(꺄악)"),
+            ("sc1e", "(yelps)", "（ひゃっ）", "(힉)"),
+            ("sc2", "(squeals)", "（きゃー）", "(꺄악)"),
+            ("sc2s", "(screams)", "（ぎゃー！）", "(으아악!)"),
+            ("sc2e", "(yelps)", "（ひゃっ）", "(힉)"),
+        ];
+
+        Self {
+            captions: captions
+                .into_iter()
+                .map(|(voice, en, ja, ko)| {
+                    (
+                        voice.to_string(),
+                        [en.to_string(), ja.to_string(), ko.to_string()],
+                    )
+                })
+                .collect(),
+        }
+    }
+}
+
+impl CaptionTable {
+    pub fn get(&self, voice: &str, language: CaptionLanguage) -> Option<&str> {
+        self.captions
+            .get(voice)
+            .map(|texts| texts[language as usize].as_str())
+    }
+}
+
+/// Voices currently playing, for the polyphony limit and group cooldowns
+#[derive(Resource)]
+pub struct VoiceManager {
+    pub playing: Vec<(Handle<AudioInstance>, VoiceKind)>,
+    pub last_played: std::collections::HashMap<VoiceKind, f32>, // Time each group last played
+    pub duck: f32, // Current gain of idle chatter (1.0 = not ducked)
+}
+
+impl Default for VoiceManager {
+    fn default() -> Self {
+        Self {
+            playing: Vec::new(),
+            last_played: std::collections::HashMap::new(),
+            duck: 1.0,
+        }
+    }
+}
+
+impl VoiceManager {
+    /// Forget voices that have finished
+    pub fn prune(&mut self, audio_instances: &Assets<AudioInstance>) {
+        self.playing.retain(|(handle, _)| {
+            audio_instances
+                .get(handle)
+                .is_some_and(|instance| instance.state() != PlaybackState::Stopped)
+        });
+    }
+
+    /// A grab or remove voice is playing, so idle chatter should duck under it
+    pub fn should_duck(&self) -> bool {
+        self.playing
+            .iter()
+            .any(|(_, kind)| matches!(kind, VoiceKind::Grab | VoiceKind::Remove))
+    }
+}
+
+/// Audio channel for speaki voices
+#[derive(Resource)]
+pub struct VoiceChannel;
+
+/// Audio channel for impact sounds (bounces and collisions)
+#[derive(Resource)]
+pub struct EffectsChannel;
+
+/// Audio channel for background music
+#[derive(Resource)]
+pub struct MusicChannel;
+
+/// A playlist entry for the background music player
+pub struct MusicTrack {
+    pub name: String,
+    pub source: Handle<AudioSource>,
+}
+
+/// Background music playlist and what is playing
+#[derive(Resource, Default)]
+pub struct MusicPlayer {
+    pub tracks: Vec<MusicTrack>, // Built-in tracks first, then the user's music folder
+    pub current: Option<usize>,
+    pub instance: Option<Handle<AudioInstance>>,
+    pub requested: Option<usize>, // Track picked in the settings, crossfaded to next frame
+    pub focus_paused: bool,       // Paused because the window lost focus
+}
+
+impl MusicPlayer {
+    /// Track after the current one, wrapping around
+    pub fn next_index(&self) -> usize {
+        self.current
+            .map_or(0, |i| (i + 1) % self.tracks.len().max(1))
+    }
+
+    /// Track before the current one, wrapping around
+    pub fn previous_index(&self) -> usize {
+        let len = self.tracks.len().max(1);
+        self.current.map_or(0, |i| (i + len - 1) % len)
+    }
+}
+
+/// Per-speaki inspector panel
+#[derive(Resource, Default)]
+pub struct Inspector {
+    pub active: bool, // Inspect mode: clicking a speaki inspects it instead of grabbing
+    pub target: Option<Entity>,
+}
+
+/// Rubber band selection in progress (Shift+drag on empty space)
+#[derive(Resource, Default)]
+pub struct SelectionBox {
+    pub start: Option<Vec2>,
+    pub end: Vec2,
+}
+
+/// Drag tracking for a single finger
+pub struct TouchDrag {
+    pub entity: Option<Entity>, // Speaki held by this finger
+    pub grab_offset: Vec2,      // Grab point in the speaki's local space
+    pub spawned: bool,          // Speaki was created by this touch (no long-press removal)
+    pub press_pos: Vec2,
+    pub press_time: f32,
+    pub moved: bool,            // Moved far enough to cancel a long press
+    pub history: DragHistory,
+}
+
+/// Two fingers resizing one speaki
+pub struct Pinch {
+    pub entity: Entity,
+    pub touches: (u64, u64),
+    pub start_distance: f32,
+    pub start_size: f32,
+}
+
+/// Touch input state: one drag per touch id, plus an optional pinch
+#[derive(Resource, Default)]
+pub struct TouchState {
+    pub drags: std::collections::HashMap<u64, TouchDrag>,
+    pub pinch: Option<Pinch>,
+}
+
+/// Stacking order of speakis (higher z is drawn and picked on top)
+#[derive(Resource, Default)]
+pub struct ZOrder {
+    pub top: f32,
+}
+
+impl ZOrder {
+    pub const STEP: f32 = 0.01;
+    pub const LIMIT: f32 = 100.0; // Well inside the 2D camera's far plane (1000)
+
+    /// Next z value above every other speaki
+    pub fn next(&mut self) -> f32 {
+        self.top += Self::STEP;
+        self.top
+    }
+}
+
+/// Global simulation speed: pause, slow motion, fast forward and single steps
+#[derive(Resource)]
+pub struct SimulationSpeed {
+    pub speed: f32,         // Multiplier while running (one of SPEEDS)
+    pub paused: bool,
+    pub pending_steps: u32, // Single steps requested while paused
+    pub step_dt: f32,       // Simulated seconds per step
+    pub step_scale: f32,    // Share of a 1x frame each step simulates (below 1 in slow motion)
+    accumulator: f32,       // Fractional steps carried over to the next frame
+}
+
+impl Default for SimulationSpeed {
+    fn default() -> Self {
+        Self {
+            speed: 1.0,
+            paused: false,
+            pending_steps: 0,
+            step_dt: 0.0,
+            step_scale: 1.0,
+            accumulator: 0.0,
+        }
+    }
+}
+
+impl SimulationSpeed {
+    pub const SPEEDS: [f32; 4] = [0.25, 0.5, 1.0, 2.0];
+
+    /// Simulated time per real time (0 while paused)
+    pub fn factor(&self) -> f32 {
+        if self.paused { 0.0 } else { self.speed }
+    }
+
+    pub fn faster(&mut self) {
+        if let Some(&speed) = Self::SPEEDS.iter().find(|&&s| s > self.speed) {
+            self.speed = speed;
+        }
+    }
+
+    pub fn slower(&mut self) {
+        if let Some(&speed) = Self::SPEEDS.iter().rev().find(|&&s| s < self.speed) {
+            self.speed = speed;
+        }
+    }
+
+    /// Pause (if running) and queue one step
+    pub fn step(&mut self) {
+        self.paused = true;
+        self.pending_steps += 1;
+    }
+
+    /// Number of simulation steps to run for a frame of `dt` seconds
+    ///
+    /// Slow motion runs one shortened step every frame so motion stays smooth;
+    /// faster speeds run whole steps, carrying fractions over to the next frame.
+    pub fn advance(&mut self, dt: f32) -> u32 {
+        self.step_scale = 1.0;
+        self.step_dt = dt;
+        if self.paused {
+            self.accumulator = 0.0;
+            return std::mem::take(&mut self.pending_steps);
+        }
+
+        if self.speed < 1.0 {
+            self.accumulator = 0.0;
+            self.step_scale = self.speed;
+            self.step_dt = dt * self.speed;
+            return 1;
+        }
+
+        self.accumulator += self.speed;
+        let steps = self.accumulator.floor();
+        self.accumulator -= steps;
+        steps as u32
+    }
+}
+
+/// Gamepad configuration
+#[derive(Resource)]
+pub struct GamepadConfig {
+    pub enabled: bool,
+    pub cursor_speed: f32,     // Virtual cursor speed at full stick (pixels per second)
+    pub tilt_enabled: bool,
+    pub max_tilt: f32,         // Maximum gravity tilt (radians)
+    pub rumble_enabled: bool,
+    pub rumble_threshold: f32, // Minimum speed for a wall bounce to rumble
+}
+
+impl Default for GamepadConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            cursor_speed: 900.0,
+            tilt_enabled: true,
+            max_tilt: std::f32::consts::FRAC_PI_2,
+            rumble_enabled: true,
+            rumble_threshold: 15.0,
+        }
+    }
+}
+
+/// Virtual grab cursor driven by the gamepad
+#[derive(Resource, Default)]
+pub struct GamepadCursor {
+    pub active: bool, // Shown once a gamepad is used
+    pub position: Vec2,
+    pub held_entity: Option<Entity>,
+    pub grab_offset: Vec2, // Grab point in the speaki's local space
+    pub history: DragHistory,
+}
+
 /// Window position tracking for inertia effect
 #[derive(Resource)]
 pub struct WindowPositionTracker {
@@ -186,32 +784,251 @@ impl Default for ImageGroups {
 #[derive(Resource, Default)]
 pub struct AudioAssets {
     pub voices: Vec<Handle<AudioSource>>,
+    pub names: Vec<String>,       // Voice file name without extension, e.g. "dontpress"
+    pub envelopes: Vec<Vec<f32>>, // Loudness per voice for lip sync (empty until loaded)
     pub loaded: bool,
 }
 
-/// Voice groups for different situations
+/// Situations a voice can be mapped to in the voice manifest
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VoiceCategory {
+    #[default]
+    Drag,
+    Create,
+    Remove,
+    Bounce,
+    HardBounce, // Bounces at or above the hard impact speed
+    Collision,  // Speakis bumping into each other
+    Idle,
+    Idle2,
+    Merge,
+    Explosion, // A shiny speaki exploding
+    WakeUp,    // A speaki that lay still for a while getting knocked about
+    ThrownFar, // Released with a fast throw
+}
+
+impl VoiceCategory {
+    pub const ALL: [VoiceCategory; 12] = [
+        VoiceCategory::Drag,
+        VoiceCategory::Create,
+        VoiceCategory::Remove,
+        VoiceCategory::Bounce,
+        VoiceCategory::HardBounce,
+        VoiceCategory::Collision,
+        VoiceCategory::Idle,
+        VoiceCategory::Idle2,
+        VoiceCategory::Merge,
+        VoiceCategory::Explosion,
+        VoiceCategory::WakeUp,
+        VoiceCategory::ThrownFar,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            VoiceCategory::Drag => "Grab",
+            VoiceCategory::Create => "Create",
+            VoiceCategory::Remove => "Remove",
+            VoiceCategory::Bounce => "Bounce",
+            VoiceCategory::HardBounce => "Hard Bounce",
+            VoiceCategory::Collision => "Collision",
+            VoiceCategory::Idle => "Idle",
+            VoiceCategory::Idle2 => "Idle 2",
+            VoiceCategory::Merge => "Merge",
+            VoiceCategory::Explosion => "Explosion",
+            VoiceCategory::WakeUp => "Wake Up",
+            VoiceCategory::ThrownFar => "Thrown Far",
+        }
+    }
+}
+
+/// A clip in a voice category, as written in the manifest
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ManifestClip {
+    pub voice: String, // Voice file name without extension
+    #[serde(default = "default_weight")]
+    pub weight: f32,
+}
+
+fn default_weight() -> f32 {
+    1.0
+}
+
+/// Voice manifest: the voice files and the clips for each category
+#[derive(Serialize, Deserialize)]
+pub struct VoiceManifest {
+    pub voices: Vec<String>, // Paths relative to the assets folder
+    pub categories: std::collections::HashMap<VoiceCategory, Vec<ManifestClip>>,
+}
+
+/// A voice and how often it is picked relative to the others in its category
+#[derive(Clone, Copy)]
+pub struct WeightedVoice {
+    pub voice: usize,
+    pub weight: f32,
+}
+
+/// Voice groups for different situations, from the voice manifest
 #[derive(Resource)]
 pub struct VoiceGroups {
-    pub drag: Vec<usize>,
-    pub bounce: Vec<usize>,
-    pub create: Vec<usize>,
-    pub remove: Vec<usize>,
-    pub idle: Vec<usize>,
-    pub idle2: Vec<usize>,
+    pub files: Vec<String>, // Voice files, indexed by voice index
+    pub names: Vec<String>, // Voice file names without extension
+    pub groups: std::collections::HashMap<VoiceCategory, Vec<WeightedVoice>>,
 }
 
 impl Default for VoiceGroups {
     fn default() -> Self {
-        // Based on config.js voice mappings
+        let manifest = Self::manifest();
+        Self::from_manifest(manifest.voices, &manifest.categories)
+    }
+}
+
+impl VoiceGroups {
+    /// Category edits from the voice mapping editor
+    const FILE: &'static str = "voices.json";
+
+    /// Manifest compiled into the game (based on config.js voice mappings)
+    fn builtin() -> VoiceManifest {
+        serde_json::from_str(include_str!("../assets/voices.json"))
+            .expect("bundled voices.json is valid")
+    }
+
+    /// `assets/voices.json` from disk, so it can be edited without a rebuild
+    /// (the compiled-in copy if it is missing or broken, and on the web)
+    fn manifest() -> VoiceManifest {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let path = bevy::asset::io::file::FileAssetReader::get_base_path()
+                .join("assets")
+                .join("voices.json");
+            if let Ok(json) = std::fs::read_to_string(&path) {
+                match serde_json::from_str(&json) {
+                    Ok(manifest) => return manifest,
+                    Err(e) => warn!("Ignoring {}: {}", path.display(), e),
+                }
+            }
+        }
+        Self::builtin()
+    }
+
+    /// Resolve clip names to voice indices, dropping clips with no matching file
+    fn from_manifest(
+        files: Vec<String>,
+        categories: &std::collections::HashMap<VoiceCategory, Vec<ManifestClip>>,
+    ) -> Self {
+        let names: Vec<String> = files
+            .iter()
+            .map(|file| {
+                std::path::Path::new(file)
+                    .file_stem()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .into_owned()
+            })
+            .collect();
+
+        let groups = categories
+            .iter()
+            .map(|(&category, clips)| {
+                let voices = clips
+                    .iter()
+                    .filter_map(|clip| {
+                        let voice = names.iter().position(|name| *name == clip.voice)?;
+                        Some(WeightedVoice {
+                            voice,
+                            weight: clip.weight,
+                        })
+                    })
+                    .collect();
+                (category, voices)
+            })
+            .collect();
+
         Self {
-            drag: vec![0, 1, 2, 3],            // dontpress, tryhard, speakifull, speakif
-            bounce: vec![16],                  // sc2e
-            create: vec![4],                   // speaki
-            remove: vec![15, 16],              // sc2s, sc2e (random)
-            idle: vec![5, 6, 7, 8, 9, 10, 11], // g1, g2, g3, gs1, gs2, gs3, gs4
-            idle2: vec![12, 14],               // sc1, sc2
+            files,
+            names,
+            groups,
         }
     }
+
+    /// Voice manifest with the saved category edits on top
+    pub fn load() -> Self {
+        let mut manifest = Self::manifest();
+        let saved: Option<std::collections::HashMap<VoiceCategory, Vec<ManifestClip>>> =
+            read_settings_file(Self::FILE).and_then(|json| serde_json::from_str(&json).ok());
+        if let Some(saved) = saved {
+            manifest.categories.extend(saved);
+        }
+        Self::from_manifest(manifest.voices, &manifest.categories)
+    }
+
+    pub fn save(&self) {
+        let categories: std::collections::HashMap<VoiceCategory, Vec<ManifestClip>> = self
+            .groups
+            .iter()
+            .map(|(&category, voices)| {
+                let clips = voices
+                    .iter()
+                    .map(|v| ManifestClip {
+                        voice: self.names[v.voice].clone(),
+                        weight: v.weight,
+                    })
+                    .collect();
+                (category, clips)
+            })
+            .collect();
+
+        if let Ok(json) = serde_json::to_string_pretty(&categories) {
+            write_settings_file(Self::FILE, &json);
+        }
+    }
+
+    pub fn clips(&self, category: VoiceCategory) -> &[WeightedVoice] {
+        self.groups.get(&category).map_or(&[], Vec::as_slice)
+    }
+
+    /// Weight of a voice in a category (`None` if it isn't in it)
+    pub fn weight(&self, category: VoiceCategory, voice: usize) -> Option<f32> {
+        self.clips(category)
+            .iter()
+            .find(|v| v.voice == voice)
+            .map(|v| v.weight)
+    }
+
+    /// Add, reweight or (with `None`) remove a voice in a category
+    pub fn set_weight(&mut self, category: VoiceCategory, voice: usize, weight: Option<f32>) {
+        let voices = self.groups.entry(category).or_default();
+        match (voices.iter_mut().find(|v| v.voice == voice), weight) {
+            (Some(existing), Some(weight)) => existing.weight = weight,
+            (None, Some(weight)) => voices.push(WeightedVoice { voice, weight }),
+            (_, None) => voices.retain(|v| v.voice != voice),
+        }
+    }
+
+    /// Random voice from a category, picked by weight
+    pub fn pick(&self, category: VoiceCategory) -> Option<usize> {
+        let voices = self.clips(category);
+        let total: f32 = voices.iter().map(|v| v.weight.max(0.0)).sum();
+        if total <= 0.0 {
+            return None;
+        }
+
+        let mut roll = rand::random::<f32>() * total;
+        for v in voices {
+            roll -= v.weight.max(0.0);
+            if roll < 0.0 {
+                return Some(v.voice);
+            }
+        }
+        voices.iter().rfind(|v| v.weight > 0.0).map(|v| v.voice)
+    }
+}
+
+/// Voice mapping editor window
+#[derive(Resource, Default)]
+pub struct VoiceEditor {
+    pub open: bool,
+    pub category: VoiceCategory, // Category being edited
 }
 
 /// Shiny speaki configuration
@@ -277,3 +1094,625 @@ impl Default for MergeConfig {
         }
     }
 }
+
+/// LAN multiplayer role of this instance
+#[derive(Resource, Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum NetMode {
+    #[default]
+    Offline,
+    Host,   // Runs the simulation and sends snapshots
+    Client, // Mirrors the host and sends input
+}
+
+/// Everything needed to bring a removed speaki back exactly as it was
+#[derive(Clone)]
+pub struct SpeakiSnapshot {
+    pub id: u64, // UndoId, stable across undo/redo
+    pub name: String,
+    pub transform: Transform,
+    pub velocity: Vec2,
+    pub rotation_speed: f32,
+    pub sprite_index: usize,
+    pub size: f32,
+    pub shiny: Option<crate::components::Shiny>,
+    pub pinned: bool,
+}
+
+/// One undoable step: speakis it removed and speakis it added
+#[derive(Clone, Default)]
+pub struct UndoEntry {
+    pub removed: Vec<SpeakiSnapshot>,
+    pub added: Vec<SpeakiSnapshot>,
+}
+
+impl UndoEntry {
+    /// The same step run backwards
+    pub fn inverted(self) -> Self {
+        Self {
+            removed: self.added,
+            added: self.removed,
+        }
+    }
+}
+
+/// Undo/redo stacks for spawns, removals and merges
+#[derive(Resource, Default)]
+pub struct UndoHistory {
+    pub undo: Vec<UndoEntry>,
+    pub redo: Vec<UndoEntry>,
+    pub next_id: u64,
+    pub replaying: bool, // Set while applying undo/redo so it isn't recorded again
+}
+
+impl UndoHistory {
+    pub const LIMIT: usize = 100;
+
+    /// Record a new step (drops the redo stack)
+    pub fn push(&mut self, entry: UndoEntry) {
+        if self.undo.len() >= Self::LIMIT {
+            self.undo.remove(0);
+        }
+        self.undo.push(entry);
+        self.redo.clear();
+    }
+}
+
+/// Rebindable keyboard action
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub enum KeyAction {
+    ToggleSettings,
+    ToggleTitlebar,
+    WindowDrag, // Held while left-clicking (native)
+    SpawnAtCursor,
+    ClearAll,
+    ToggleMerge,
+    Mute,
+    PausePhysics,
+    SlowDown,
+    SpeedUp,
+    Step,
+    Inspect,
+    Screenshot,
+    SelectAll,
+    ClearSelection,
+    DeleteSelected,
+    GrowSelected,
+    ShrinkSelected,
+    Undo,
+    Redo,
+}
+
+impl KeyAction {
+    pub const ALL: [KeyAction; 20] = [
+        KeyAction::ToggleSettings,
+        KeyAction::ToggleTitlebar,
+        KeyAction::WindowDrag,
+        KeyAction::SpawnAtCursor,
+        KeyAction::ClearAll,
+        KeyAction::ToggleMerge,
+        KeyAction::Mute,
+        KeyAction::PausePhysics,
+        KeyAction::SlowDown,
+        KeyAction::SpeedUp,
+        KeyAction::Step,
+        KeyAction::Inspect,
+        KeyAction::Screenshot,
+        KeyAction::SelectAll,
+        KeyAction::ClearSelection,
+        KeyAction::DeleteSelected,
+        KeyAction::GrowSelected,
+        KeyAction::ShrinkSelected,
+        KeyAction::Undo,
+        KeyAction::Redo,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            KeyAction::ToggleSettings => "Settings",
+            KeyAction::ToggleTitlebar => "Title Bar",
+            KeyAction::WindowDrag => "Window Drag",
+            KeyAction::SpawnAtCursor => "Spawn at Cursor",
+            KeyAction::ClearAll => "Clear All",
+            KeyAction::ToggleMerge => "Merge Mode",
+            KeyAction::Mute => "Mute",
+            KeyAction::PausePhysics => "Pause",
+            KeyAction::SlowDown => "Slower",
+            KeyAction::SpeedUp => "Faster",
+            KeyAction::Step => "Single Step",
+            KeyAction::Inspect => "Inspect Mode",
+            KeyAction::Screenshot => "Screenshot",
+            KeyAction::SelectAll => "Select All",
+            KeyAction::ClearSelection => "Clear Selection",
+            KeyAction::DeleteSelected => "Delete Selected",
+            KeyAction::GrowSelected => "Grow Selected",
+            KeyAction::ShrinkSelected => "Shrink Selected",
+            KeyAction::Undo => "Undo",
+            KeyAction::Redo => "Redo",
+        }
+    }
+}
+
+/// A key plus the modifiers held with it
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct KeyChord {
+    pub key: KeyCode,
+    #[serde(default)]
+    pub ctrl: bool,
+    #[serde(default)]
+    pub alt: bool,
+    #[serde(default)]
+    pub altgr: bool, // Right Alt, which types symbols on many layouts
+    #[serde(default)]
+    pub shift: bool,
+}
+
+impl KeyChord {
+    pub const CTRL: [KeyCode; 2] = [KeyCode::ControlLeft, KeyCode::ControlRight];
+    pub const SHIFT: [KeyCode; 2] = [KeyCode::ShiftLeft, KeyCode::ShiftRight];
+    pub const ALT: KeyCode = KeyCode::AltLeft;
+    pub const ALTGR: KeyCode = KeyCode::AltRight;
+
+    pub const fn new(key: KeyCode) -> Self {
+        Self {
+            key,
+            ctrl: false,
+            alt: false,
+            altgr: false,
+            shift: false,
+        }
+    }
+
+    /// A key with the modifiers currently held. Windows sends AltGr as
+    /// Left Ctrl + Right Alt, so Left Ctrl doesn't count while AltGr is down
+    pub fn with_modifiers(key: KeyCode, keyboard: &ButtonInput<KeyCode>) -> Self {
+        let altgr = keyboard.pressed(Self::ALTGR);
+        Self {
+            key,
+            ctrl: keyboard.pressed(KeyCode::ControlRight)
+                || (keyboard.pressed(KeyCode::ControlLeft) && !altgr),
+            alt: keyboard.pressed(Self::ALT),
+            altgr,
+            shift: keyboard.any_pressed(Self::SHIFT),
+        }
+    }
+
+    /// Both sides of a held modifier key, so Right Alt (and AltGr) can drag
+    /// the window too; in chords AltGr is its own modifier
+    fn sides(key: KeyCode) -> [KeyCode; 2] {
+        [Self::CTRL, [Self::ALT, Self::ALTGR], Self::SHIFT]
+            .into_iter()
+            .find(|sides| sides.contains(&key))
+            .unwrap_or([key, key])
+    }
+
+    pub fn is_modifier(key: KeyCode) -> bool {
+        Self::sides(key) != [key, key]
+    }
+
+    /// Pressed this frame with exactly these modifiers
+    pub fn just_pressed(&self, keyboard: &ButtonInput<KeyCode>) -> bool {
+        keyboard.just_pressed(self.key) && Self::with_modifiers(self.key, keyboard) == *self
+    }
+
+    /// Held down (modifiers held with it are ignored)
+    pub fn pressed(&self, keyboard: &ButtonInput<KeyCode>) -> bool {
+        keyboard.any_pressed(Self::sides(self.key))
+    }
+
+    pub fn label(&self) -> String {
+        let mut label = String::new();
+        if self.ctrl {
+            label.push_str("Ctrl+");
+        }
+        if self.alt {
+            label.push_str("Alt+");
+        }
+        if self.altgr {
+            label.push_str("AltGr+");
+        }
+        if self.shift {
+            label.push_str("Shift+");
+        }
+
+        let key = format!("{:?}", self.key);
+        let key = key
+            .strip_prefix("Key")
+            .or_else(|| key.strip_prefix("Digit"))
+            .unwrap_or(&key);
+        label.push_str(key);
+        label
+    }
+}
+
+/// Keyboard shortcuts, editable in Settings → Controls and saved between runs
+#[derive(Resource, Clone)]
+pub struct KeyBindings {
+    pub bindings: std::collections::HashMap<KeyAction, KeyChord>,
+    pub listening: Option<KeyAction>, // Action waiting for a new key
+    pub conflict: Option<KeyAction>,  // Action already using the last key pressed
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        let bindings = KeyAction::ALL
+            .into_iter()
+            .map(|action| (action, Self::default_chord(action)))
+            .collect();
+
+        Self {
+            bindings,
+            listening: None,
+            conflict: None,
+        }
+    }
+}
+
+impl KeyBindings {
+    const FILE: &'static str = "keybindings.json";
+
+    pub fn default_chord(action: KeyAction) -> KeyChord {
+        match action {
+            KeyAction::ToggleSettings => KeyChord::new(KeyCode::KeyQ),
+            KeyAction::ToggleTitlebar => KeyChord {
+                alt: true,
+                ..KeyChord::new(KeyCode::KeyT)
+            },
+            KeyAction::WindowDrag => KeyChord::new(KeyCode::AltLeft),
+            KeyAction::SpawnAtCursor => KeyChord::new(KeyCode::Space),
+            KeyAction::ClearAll => KeyChord {
+                shift: true,
+                ..KeyChord::new(KeyCode::KeyC)
+            },
+            KeyAction::ToggleMerge => KeyChord::new(KeyCode::KeyG),
+            KeyAction::Mute => KeyChord::new(KeyCode::KeyM),
+            KeyAction::PausePhysics => KeyChord::new(KeyCode::KeyP),
+            KeyAction::SlowDown => KeyChord::new(KeyCode::BracketLeft),
+            KeyAction::SpeedUp => KeyChord::new(KeyCode::BracketRight),
+            KeyAction::Step => KeyChord::new(KeyCode::Period),
+            KeyAction::Inspect => KeyChord::new(KeyCode::KeyI),
+            KeyAction::Screenshot => KeyChord::new(KeyCode::F12),
+            KeyAction::SelectAll => KeyChord {
+                ctrl: true,
+                ..KeyChord::new(KeyCode::KeyA)
+            },
+            KeyAction::ClearSelection => KeyChord::new(KeyCode::Escape),
+            KeyAction::DeleteSelected => KeyChord::new(KeyCode::Delete),
+            KeyAction::GrowSelected => KeyChord::new(KeyCode::Equal),
+            KeyAction::ShrinkSelected => KeyChord::new(KeyCode::Minus),
+            KeyAction::Undo => KeyChord {
+                ctrl: true,
+                ..KeyChord::new(KeyCode::KeyZ)
+            },
+            KeyAction::Redo => KeyChord {
+                ctrl: true,
+                shift: true,
+                ..KeyChord::new(KeyCode::KeyZ)
+            },
+        }
+    }
+
+    pub fn chord(&self, action: KeyAction) -> KeyChord {
+        self.bindings
+            .get(&action)
+            .copied()
+            .unwrap_or_else(|| Self::default_chord(action))
+    }
+
+    pub fn just_pressed(&self, action: KeyAction, keyboard: &ButtonInput<KeyCode>) -> bool {
+        self.listening.is_none() && self.chord(action).just_pressed(keyboard)
+    }
+
+    pub fn pressed(&self, action: KeyAction, keyboard: &ButtonInput<KeyCode>) -> bool {
+        self.chord(action).pressed(keyboard)
+    }
+
+    /// Another action already bound to this chord
+    pub fn conflict(&self, action: KeyAction, chord: KeyChord) -> Option<KeyAction> {
+        KeyAction::ALL
+            .into_iter()
+            .find(|&other| other != action && self.chord(other) == chord)
+    }
+
+    /// Saved bindings on top of the defaults
+    pub fn load() -> Self {
+        let mut key_bindings = Self::default();
+        let saved: Option<std::collections::HashMap<KeyAction, KeyChord>> =
+            read_settings_file(Self::FILE).and_then(|json| serde_json::from_str(&json).ok());
+        if let Some(saved) = saved {
+            key_bindings.bindings.extend(saved);
+        }
+        key_bindings
+    }
+
+    pub fn save(&self) {
+        if let Ok(json) = serde_json::to_string_pretty(&self.bindings) {
+            write_settings_file(Self::FILE, &json);
+        }
+    }
+}
+
+/// Per-user settings directory (native only)
+#[cfg(not(target_arch = "wasm32"))]
+fn settings_dir() -> Option<std::path::PathBuf> {
+    use std::path::PathBuf;
+
+    let base = if cfg!(windows) {
+        std::env::var_os("APPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        std::env::var_os("HOME").map(|home| PathBuf::from(home).join("Library/Application Support"))
+    } else {
+        std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+    };
+    base.map(|dir| dir.join("speaki-box"))
+}
+
+/// Folder the music player picks up the user's own tracks from (native only)
+#[cfg(not(target_arch = "wasm32"))]
+pub fn music_dir() -> Option<std::path::PathBuf> {
+    settings_dir().map(|dir| dir.join("music"))
+}
+
+/// Read a settings file (`None` on the web or if it doesn't exist yet)
+pub fn read_settings_file(name: &str) -> Option<String> {
+    #[cfg(not(target_arch = "wasm32"))]
+    return std::fs::read_to_string(settings_dir()?.join(name)).ok();
+
+    #[cfg(target_arch = "wasm32")]
+    {
+        let _ = name;
+        None
+    }
+}
+
+/// Write a settings file (no-op on the web)
+pub fn write_settings_file(name: &str, contents: &str) {
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some(dir) = settings_dir() {
+        let result =
+            std::fs::create_dir_all(&dir).and_then(|_| std::fs::write(dir.join(name), contents));
+        if let Err(e) = result {
+            warn!("Failed to save {}: {}", name, e);
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    let _ = (name, contents);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn drag(samples: &[(f32, Vec2)]) -> DragHistory {
+        let mut history = DragHistory::default();
+        history.start(samples[0].0, samples[0].1);
+        for &(time, position) in &samples[1..] {
+            history.push(time, position);
+        }
+        history
+    }
+
+    #[test]
+    fn linear_drag_velocity() {
+        // 100 px/s to the right, 50 px/s up
+        let samples: Vec<(f32, Vec2)> = (0..6)
+            .map(|i| {
+                let t = i as f32 / 60.0;
+                (t, Vec2::new(100.0, 50.0) * t)
+            })
+            .collect();
+        let history = drag(&samples);
+        let now = samples[5].0;
+
+        let velocity = history.velocity(now);
+        let expected = Vec2::new(100.0, 50.0) * DragHistory::TIME_SCALE;
+        assert!((velocity - expected).length() < 1e-3, "{velocity}");
+        assert!(history.curvature(now).abs() < 1e-3);
+    }
+
+    #[test]
+    fn old_samples_are_ignored() {
+        // A stop long ago, then a steady drag to the left
+        let mut samples = vec![(0.0, Vec2::new(500.0, 0.0))];
+        samples.extend((0..6).map(|i| {
+            let t = 1.0 + i as f32 / 60.0;
+            (t, Vec2::new(-200.0 * (t - 1.0), 0.0))
+        }));
+        let history = drag(&samples);
+
+        let velocity = history.velocity(samples[6].0);
+        assert!((velocity.x + 200.0 * DragHistory::TIME_SCALE).abs() < 1e-3);
+        assert!(velocity.y.abs() < 1e-3);
+    }
+
+    #[test]
+    fn curved_drag_turns() {
+        // Quarter circle, counterclockwise
+        let samples: Vec<(f32, Vec2)> = (0..6)
+            .map(|i| {
+                let angle = i as f32 / 5.0 * std::f32::consts::FRAC_PI_2;
+                (i as f32 / 60.0, Vec2::from_angle(angle) * 100.0)
+            })
+            .collect();
+        let history = drag(&samples);
+        let now = samples[5].0;
+
+        let curvature = history.curvature(now);
+        assert!(curvature > 0.0);
+        // Four turns between five chords of the arc
+        let expected = std::f32::consts::FRAC_PI_2 * 4.0 / 5.0;
+        assert!((curvature - expected).abs() < 1e-3, "{curvature}");
+
+        let reversed = drag(
+            &samples
+                .iter()
+                .map(|&(t, p)| (t, Vec2::new(p.x, -p.y)))
+                .collect::<Vec<_>>(),
+        );
+        assert!((reversed.curvature(now) + expected).abs() < 1e-3);
+    }
+
+    #[test]
+    fn single_sample_is_still() {
+        let history = drag(&[(1.0, Vec2::new(10.0, 20.0))]);
+        assert_eq!(history.velocity(1.0), Vec2::ZERO);
+        assert_eq!(history.curvature(1.0), 0.0);
+    }
+
+    #[test]
+    fn identical_timestamps_keep_newest() {
+        let mut history = drag(&[
+            (1.0, Vec2::ZERO),
+            (1.0, Vec2::new(50.0, 0.0)),
+            (1.0, Vec2::new(100.0, 0.0)),
+        ]);
+        // One sample left, so no speed estimate (and no division by zero)
+        assert_eq!(history.velocity(1.0), Vec2::ZERO);
+
+        history.push(1.0 + 1.0 / 60.0, Vec2::new(110.0, 0.0));
+        let velocity = history.velocity(1.0 + 1.0 / 60.0);
+        assert!(
+            (velocity.x - 600.0 * DragHistory::TIME_SCALE).abs() < 1e-2,
+            "{velocity}"
+        );
+    }
+
+    #[test]
+    fn simulation_steps_per_frame() {
+        let mut sim_speed = SimulationSpeed::default();
+        let frames = |sim_speed: &mut SimulationSpeed| -> Vec<u32> {
+            (0..4).map(|_| sim_speed.advance(0.01)).collect()
+        };
+
+        assert_eq!(frames(&mut sim_speed), [1, 1, 1, 1]);
+        assert_eq!(sim_speed.step_scale, 1.0);
+
+        sim_speed.speed = 2.0;
+        assert_eq!(frames(&mut sim_speed), [2, 2, 2, 2]);
+
+        // Slow motion steps every frame, just shorter
+        sim_speed.speed = 0.25;
+        assert_eq!(frames(&mut sim_speed), [1, 1, 1, 1]);
+        assert_eq!(sim_speed.step_scale, 0.25);
+        assert!((sim_speed.step_dt - 0.0025).abs() < 1e-6);
+
+        sim_speed.paused = true;
+        assert_eq!(frames(&mut sim_speed), [0, 0, 0, 0]);
+        sim_speed.step();
+        assert_eq!(frames(&mut sim_speed), [1, 0, 0, 0]);
+        assert_eq!(sim_speed.step_scale, 1.0);
+    }
+
+    fn keyboard(held: &[KeyCode], pressed: KeyCode) -> ButtonInput<KeyCode> {
+        let mut keyboard = ButtonInput::default();
+        for &key in held {
+            keyboard.press(key);
+        }
+        keyboard.clear();
+        keyboard.press(pressed);
+        keyboard
+    }
+
+    #[test]
+    fn chord_parses_with_missing_modifiers() {
+        let chord: KeyChord = serde_json::from_str(r#"{"key":"KeyT","alt":true}"#).unwrap();
+        assert_eq!(
+            chord,
+            KeyChord {
+                alt: true,
+                ..KeyChord::new(KeyCode::KeyT)
+            }
+        );
+
+        let json = serde_json::to_string(&chord).unwrap();
+        assert_eq!(serde_json::from_str::<KeyChord>(&json).unwrap(), chord);
+    }
+
+    #[test]
+    fn chord_labels() {
+        let chord = KeyChord {
+            ctrl: true,
+            shift: true,
+            ..KeyChord::new(KeyCode::KeyZ)
+        };
+        assert_eq!(chord.label(), "Ctrl+Shift+Z");
+        assert_eq!(KeyChord::new(KeyCode::Digit1).label(), "1");
+        assert_eq!(KeyChord::new(KeyCode::F12).label(), "F12");
+    }
+
+    #[test]
+    fn chord_needs_exact_modifiers() {
+        let chord = KeyChord {
+            shift: true,
+            ..KeyChord::new(KeyCode::KeyC)
+        };
+        assert!(chord.just_pressed(&keyboard(&[KeyCode::ShiftRight], KeyCode::KeyC)));
+        assert!(!chord.just_pressed(&keyboard(&[], KeyCode::KeyC)));
+        assert!(!chord.just_pressed(&keyboard(
+            &[KeyCode::ShiftLeft, KeyCode::ControlLeft],
+            KeyCode::KeyC
+        )));
+        assert!(
+            !KeyChord::new(KeyCode::KeyC)
+                .just_pressed(&keyboard(&[KeyCode::ShiftLeft], KeyCode::KeyC))
+        );
+    }
+
+    #[test]
+    fn altgr_is_not_ctrl_alt() {
+        // Windows reports AltGr as Left Ctrl + Right Alt
+        let keys = keyboard(&[KeyCode::ControlLeft, KeyCode::AltRight], KeyCode::KeyQ);
+        let chord = KeyChord::with_modifiers(KeyCode::KeyQ, &keys);
+        assert_eq!(
+            chord,
+            KeyChord {
+                altgr: true,
+                ..KeyChord::new(KeyCode::KeyQ)
+            }
+        );
+        assert_eq!(chord.label(), "AltGr+Q");
+
+        let ctrl_alt = KeyChord {
+            ctrl: true,
+            alt: true,
+            ..KeyChord::new(KeyCode::KeyQ)
+        };
+        assert!(!ctrl_alt.just_pressed(&keys));
+        assert!(ctrl_alt.just_pressed(&keyboard(
+            &[KeyCode::ControlLeft, KeyCode::AltLeft],
+            KeyCode::KeyQ
+        )));
+    }
+
+    #[test]
+    fn held_modifier_matches_either_side() {
+        let chord = KeyChord::new(KeyCode::ShiftLeft);
+        assert!(chord.pressed(&keyboard(&[KeyCode::ShiftRight], KeyCode::KeyA)));
+        assert!(
+            KeyChord::new(KeyCode::AltLeft)
+                .pressed(&keyboard(&[KeyCode::AltRight], KeyCode::KeyA))
+        );
+        assert!(KeyChord::is_modifier(KeyCode::AltRight));
+        assert!(!KeyChord::is_modifier(KeyCode::KeyA));
+    }
+
+    #[test]
+    fn duplicate_chord_conflicts() {
+        let bindings = KeyBindings::default();
+        let mute = bindings.chord(KeyAction::Mute);
+        assert_eq!(
+            bindings.conflict(KeyAction::ToggleMerge, mute),
+            Some(KeyAction::Mute)
+        );
+        // Rebinding an action to its own key is fine
+        assert_eq!(bindings.conflict(KeyAction::Mute, mute), None);
+        // Same key with a modifier is a different chord
+        let shifted = KeyChord {
+            shift: true,
+            ..mute
+        };
+        assert_eq!(bindings.conflict(KeyAction::ToggleMerge, shifted), None);
+    }
+}
@@ -1,9 +1,13 @@
+use bevy::picking::hover::Hovered;
 use bevy::prelude::*;
 use rand::RngExt;
 
 use crate::components::*;
 use crate::resources::*;
 
+/// Brightness boost of the speaki under the cursor (HDR, so it blooms slightly)
+const HOVER_BRIGHTNESS: f32 = 1.3;
+
 /// Handle eye blinking animation
 pub fn blink_system(
     mut query: Query<(&mut SpriteState, &mut BlinkTimer), With<Speaki>>,
@@ -24,10 +28,10 @@ pub fn blink_system(
                 // Open eyes
                 blink.is_blinking = false;
 
-                if let Some(state) = sprites.states.get(sprite_state.current_index) {
-                    if let Some(open_idx) = state.eye_open {
-                        sprite_state.current_index = open_idx;
-                    }
+                if let Some(state) = sprites.states.get(sprite_state.current_index)
+                    && let Some(open_idx) = state.eye_open
+                {
+                    sprite_state.current_index = open_idx;
                 }
 
                 if blink.double_blink {
@@ -48,10 +52,10 @@ pub fn blink_system(
                 blink.is_blinking = true;
                 blink.blink_open_time = 0.1; // 100ms
 
-                if let Some(state) = sprites.states.get(sprite_state.current_index) {
-                    if let Some(close_idx) = state.eye_close {
-                        sprite_state.current_index = close_idx;
-                    }
+                if let Some(state) = sprites.states.get(sprite_state.current_index)
+                    && let Some(close_idx) = state.eye_close
+                {
+                    sprite_state.current_index = close_idx;
                 }
 
                 blink.last_blink_time = current_time;
@@ -65,12 +69,12 @@ pub fn blink_system(
     }
 }
 
+/// Speakis whose sprite state changed since the last redraw
+type RedrawnSpeaki = (With<Speaki>, Changed<SpriteState>);
+
 /// Update sprite images based on state
 pub fn sprite_update_system(
-    mut query: Query<
-        (&SpriteState, &SpeakiSize, &mut Sprite),
-        (With<Speaki>, Changed<SpriteState>),
-    >,
+    mut query: Query<(&SpriteState, &SpeakiSize, &mut Sprite), RedrawnSpeaki>,
     sprites: Res<SpriteAssets>,
 ) {
     if !sprites.loaded {
@@ -112,19 +116,27 @@ pub fn change_to_normal_system(
 
 /// Animate shiny speakis with pulsing glow effect
 pub fn shiny_glow_system(
-    mut query: Query<(&Shiny, &mut Sprite), With<Speaki>>,
+    mut query: Query<(&Shiny, &mut Sprite, &Hovered), With<Speaki>>,
     shiny_config: Res<ShinyConfig>,
+    config: Res<GameConfig>,
     time: Res<Time>,
 ) {
     let elapsed = time.elapsed_secs();
 
-    for (shiny, mut sprite) in query.iter_mut() {
+    for (shiny, mut sprite, hovered) in query.iter_mut() {
         // Calculate pulse factor (oscillates between 0.7 and 1.0)
         let pulse = ((elapsed * shiny_config.pulse_speed + shiny.pulse_phase).sin() + 1.0) / 2.0;
         let pulse_factor = 0.7 + pulse * 0.3;
 
+        // Glow a bit brighter while hovered
+        let hover_factor = if config.hover_highlight && hovered.get() {
+            HOVER_BRIGHTNESS
+        } else {
+            1.0
+        };
+
         // Apply HDR color with pulsing intensity (use each shiny's own color)
-        let intensity = shiny_config.glow_intensity * pulse_factor;
+        let intensity = shiny_config.glow_intensity * pulse_factor * hover_factor;
         let base = shiny.base_color.to_srgba();
         sprite.color = Color::srgb(
             base.red * intensity,
@@ -133,3 +145,25 @@ pub fn shiny_glow_system(
         );
     }
 }
+
+/// Speakis whose tint is free for the hover glow (shiny ones pulse their own color)
+type PlainSpeaki = (With<Speaki>, Without<Shiny>);
+
+/// Highlight the (non-shiny) speaki under the cursor
+pub fn hover_highlight_system(
+    mut query: Query<(&Hovered, &mut Sprite), PlainSpeaki>,
+    config: Res<GameConfig>,
+) {
+    for (hovered, mut sprite) in query.iter_mut() {
+        let target = if config.hover_highlight && hovered.get() {
+            Color::srgb(HOVER_BRIGHTNESS, HOVER_BRIGHTNESS, HOVER_BRIGHTNESS)
+        } else {
+            Color::WHITE
+        };
+
+        // Only write on change to keep change detection quiet
+        if sprite.color != target {
+            sprite.color = target;
+        }
+    }
+}
@@ -1,86 +1,464 @@
+use bevy::ecs::system::SystemParam;
 use bevy::prelude::*;
 use bevy_kira_audio::prelude::*;
 use rand::RngExt;
+use std::collections::HashMap;
+use std::time::Duration;
 
 use crate::components::*;
 use crate::events::*;
 use crate::resources::*;
 
-/// Handle voice play events
+/// Quietest a voice gets from cursor distance attenuation
+const MIN_CURSOR_GAIN: f32 = 0.2;
+/// Volume of the gentlest impact that still makes a sound, relative to a hard one
+const MIN_IMPACT_GAIN: f32 = 0.25;
+/// Seconds for idle chatter to duck fully (or recover)
+const DUCK_TIME: f32 = 0.15;
+/// Length (seconds) of each lip sync loudness window
+const ENVELOPE_STEP: f32 = 0.03;
+/// Loudness below the open threshold (as a fraction of it) that closes the mouth again
+const LIP_SYNC_HYSTERESIS: f32 = 0.6;
+/// Fade applied when a voice is cut off by another one
+const VOICE_CUT_FADE: Duration = Duration::from_millis(60);
+/// Speed (px/frame) below which a speaki counts as lying still
+const WAKE_SPEED: f32 = 1.0;
+
+/// Convert amplitude (0.0-1.0) to decibels
+/// dB = 20 * log10(amplitude), with minimum of -80dB for silence
+fn amplitude_to_db(amplitude: f32) -> f32 {
+    if amplitude > 0.001 {
+        20.0 * amplitude.log10()
+    } else {
+        -80.0 // Effectively silent
+    }
+}
+
+/// Gain for a voice of this kind under the current ducking
+fn duck_gain(kind: VoiceKind, voice_manager: &VoiceManager) -> f32 {
+    if kind == VoiceKind::Idle {
+        voice_manager.duck
+    } else {
+        1.0
+    }
+}
+
+/// Volume scale for an impact at this speed (pixels per frame)
+fn impact_gain(speed: f32, audio_config: &AudioConfig) -> f32 {
+    if !audio_config.impact_volume {
+        return 1.0;
+    }
+    (speed / audio_config.hard_impact_speed.max(1.0)).clamp(MIN_IMPACT_GAIN, 1.0)
+}
+
+/// Playback rate for a voice: deeper for big speakis, squeakier for small ones,
+/// plus a little random variation so repeated voices don't sound identical
+fn voice_pitch(size: Option<f32>, reference_size: f32, audio_config: &AudioConfig) -> f64 {
+    let size_pitch = match size {
+        Some(size) if audio_config.size_pitch && size > 0.0 => (reference_size / size)
+            .powf(0.5)
+            .clamp(audio_config.min_pitch, audio_config.max_pitch),
+        _ => 1.0,
+    };
+    let variation = (rand::random::<f32>() * 2.0 - 1.0) * audio_config.pitch_variation;
+    (size_pitch * (1.0 + variation)) as f64
+}
+
+/// Where voices are heard from: the box for panning, the cursor for attenuation
+struct Listener {
+    bounds: Rect,
+    cursor: Option<Vec2>,
+}
+
+impl Listener {
+    fn new(
+        window: &Window,
+        camera_q: &Query<(&Camera, &GlobalTransform)>,
+        border: &BorderConfig,
+    ) -> Self {
+        let cursor = camera_q
+            .single()
+            .ok()
+            .and_then(|(camera, camera_transform)| {
+                window
+                    .cursor_position()
+                    .and_then(|p| camera.viewport_to_world_2d(camera_transform, p).ok())
+            });
+
+        Self {
+            bounds: border.bounds(window.width(), window.height()),
+            cursor,
+        }
+    }
+
+    /// Stereo position from x in the box (-1 = left, 1 = right)
+    fn panning(&self, position: Vec2, audio_config: &AudioConfig) -> f32 {
+        if !audio_config.stereo_panning {
+            return 0.0;
+        }
+        let half_width = (self.bounds.width() / 2.0).max(1.0);
+        let offset = (position.x - self.bounds.center().x) / half_width;
+        offset.clamp(-1.0, 1.0) * audio_config.pan_width
+    }
+
+    /// Volume factor from the distance to the cursor
+    fn gain(&self, position: Vec2, audio_config: &AudioConfig) -> f32 {
+        match self.cursor {
+            Some(cursor) if audio_config.cursor_attenuation => {
+                let t = (position.distance(cursor) / audio_config.attenuation_distance).min(1.0);
+                1.0 - t * (1.0 - MIN_CURSOR_GAIN)
+            }
+            _ => 1.0,
+        }
+    }
+}
+
+/// Window, camera and border a `Listener` is built from
+#[derive(SystemParam)]
+pub struct ListenerParams<'w, 's> {
+    window: Single<'w, 's, &'static Window>,
+    camera_q: Query<'w, 's, (&'static Camera, &'static GlobalTransform)>,
+    border: Res<'w, BorderConfig>,
+}
+
+impl ListenerParams<'_, '_> {
+    fn listener(&self) -> Listener {
+        Listener::new(&self.window, &self.camera_q, &self.border)
+    }
+}
+
+/// Writer plus voice groups and volumes for systems that play interaction voices
+#[derive(SystemParam)]
+pub struct Voices<'w> {
+    pub events: MessageWriter<'w, PlayVoiceEvent>,
+    pub groups: Res<'w, VoiceGroups>,
+    pub config: Res<'w, AudioConfig>,
+}
+
+/// Clips, channels and live instances voices play through
+#[derive(SystemParam)]
+pub struct VoicePlayback<'w> {
+    audio_assets: Res<'w, AudioAssets>,
+    voice_channel: Res<'w, AudioChannel<VoiceChannel>>,
+    effects_channel: Res<'w, AudioChannel<EffectsChannel>>,
+    audio_instances: ResMut<'w, Assets<AudioInstance>>,
+    voice_manager: ResMut<'w, VoiceManager>,
+}
+
+/// Handle voice play events through the voice manager: a polyphony limit,
+/// group cooldowns, and one voice per speaki
 pub fn play_voice_system(
     mut events: MessageReader<PlayVoiceEvent>,
-    audio_assets: Res<AudioAssets>,
     audio_config: Res<AudioConfig>,
-    audio: Res<Audio>,
-    mut speaki_query: Query<(&mut CurrentAudio, &mut SpriteState), With<Speaki>>,
-    sprites: Res<SpriteAssets>,
+    playback: VoicePlayback,
+    mut speaki_query: Query<
+        (&Transform, &SpeakiSize, &mut CurrentAudio, &mut SpriteState),
+        With<Speaki>,
+    >,
+    (sprites, config): (Res<SpriteAssets>, Res<GameConfig>),
+    listener_params: ListenerParams,
+    time: Res<Time>,
 ) {
+    let VoicePlayback {
+        audio_assets,
+        voice_channel,
+        effects_channel,
+        mut audio_instances,
+        mut voice_manager,
+    } = playback;
+
     if !audio_assets.loaded {
         return;
     }
 
-    for event in events.read() {
-        if let Some(source_handle) = audio_assets.voices.get(event.voice_index) {
-            // Convert amplitude (0.0-1.0) to decibels
-            // dB = 20 * log10(amplitude), with minimum of -80dB for silence
-            let amplitude = audio_config.master_volume * event.volume;
-            let volume_db: f32 = if amplitude > 0.001 {
-                20.0 * amplitude.log10()
-            } else {
-                -80.0 // Effectively silent
+    let current_time = time.elapsed_secs();
+    let listener = listener_params.listener();
+
+    voice_manager.prune(&audio_instances);
+
+    // Most important voices first, so they get the free slots
+    let mut pending: Vec<&PlayVoiceEvent> = events.read().collect();
+    pending.sort_by_key(|event| std::cmp::Reverse(event.kind.priority()));
+
+    for event in pending {
+        let Some(source_handle) = audio_assets.voices.get(event.voice_index) else {
+            continue;
+        };
+        let priority = event.kind.priority();
+
+        // A burst of bounces (or idles, removals...) only voices the first one;
+        // grabs and direct requests always answer
+        if priority < VoiceKind::Grab.priority()
+            && voice_manager
+                .last_played
+                .get(&event.kind)
+                .is_some_and(|&last| current_time - last < audio_config.group_cooldown)
+        {
+            continue;
+        }
+
+        let speaki = event
+            .entity
+            .and_then(|entity| speaki_query.get_mut(entity).ok());
+
+        // A speaki says one thing at a time: keep its current voice if that one
+        // matters more (or just started), otherwise cut it off
+        if let Some((_, _, current_audio, _)) = &speaki
+            && let Some(handle) = &current_audio.handle
+            && let Some(current_kind) = current_audio.kind
+        {
+            let recent = current_time - current_audio.started < audio_config.voice_cooldown;
+            if current_kind.priority() > priority || (recent && current_kind.priority() == priority)
+            {
+                continue;
+            }
+
+            if let Some(instance) = audio_instances.get_mut(handle) {
+                instance.stop(AudioTween::linear(VOICE_CUT_FADE));
+            }
+            voice_manager
+                .playing
+                .retain(|(playing, _)| playing != handle);
+        }
+
+        // At the limit, make room by cutting the least important (oldest) voice
+        if voice_manager.playing.len() >= audio_config.max_voices {
+            let Some(weakest) = voice_manager
+                .playing
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, (_, kind))| kind.priority())
+                .filter(|(_, (_, kind))| kind.priority() < priority)
+                .map(|(i, _)| i)
+            else {
+                continue;
             };
-            let instance_handle = audio
-                .play(source_handle.clone())
-                .with_volume(volume_db)
-                .handle();
-
-            // If this voice is for a specific speaki, track it and open mouth
-            if let Some(entity) = event.entity {
-                if let Ok((mut current_audio, mut sprite_state)) = speaki_query.get_mut(entity) {
-                    current_audio.handle = Some(instance_handle);
-
-                    // Open mouth if current image has mouth_open state
-                    if let Some(state) = sprites.states.get(sprite_state.current_index) {
-                        if let Some(mouth_open_idx) = state.mouth_open {
-                            sprite_state.current_index = mouth_open_idx;
-                        }
-                    }
-                }
+
+            let (handle, _) = voice_manager.playing.remove(weakest);
+            if let Some(instance) = audio_instances.get_mut(&handle) {
+                instance.stop(AudioTween::linear(VOICE_CUT_FADE));
             }
         }
+
+        // Voices without a speaki play centered at full volume
+        let (panning, gain) = speaki.as_ref().map_or((0.0, 1.0), |(transform, ..)| {
+            let position = transform.translation.truncate();
+            (
+                listener.panning(position, &audio_config),
+                listener.gain(position, &audio_config),
+            )
+        });
+
+        // Default-size speakis keep their natural pitch
+        let size = speaki.as_ref().map(|(_, size, ..)| size.0);
+        let pitch = voice_pitch(size, config.speaki_size, &audio_config);
+
+        // Impacts go to the effects channel, everything else is a voice
+        let amplitude = event.volume * gain * duck_gain(event.kind, &voice_manager);
+        let mut command = if event.kind.is_effect() {
+            effects_channel.play(source_handle.clone())
+        } else {
+            voice_channel.play(source_handle.clone())
+        };
+        let instance_handle = command
+            .with_volume(amplitude_to_db(amplitude))
+            .with_panning(panning)
+            .with_playback_rate(pitch)
+            .handle();
+
+        voice_manager
+            .playing
+            .push((instance_handle.clone(), event.kind));
+        voice_manager.last_played.insert(event.kind, current_time);
+
+        // If this voice is for a specific speaki, track it and open mouth
+        if let Some((_, _, mut current_audio, mut sprite_state)) = speaki {
+            current_audio.handle = Some(instance_handle);
+            current_audio.voice_index = Some(event.voice_index);
+            current_audio.volume = event.volume;
+            current_audio.kind = Some(event.kind);
+            current_audio.started = current_time;
+
+            // Open mouth if current image has mouth_open state
+            set_mouth(&mut sprite_state, &sprites, true);
+        }
+    }
+}
+
+/// Keep playing voices panned, attenuated and ducked as their speaki moves
+pub fn voice_spatial_system(
+    query: Query<(&Transform, &CurrentAudio), With<Speaki>>,
+    mut audio_instances: ResMut<Assets<AudioInstance>>,
+    mut voice_manager: ResMut<VoiceManager>,
+    audio_config: Res<AudioConfig>,
+    listener_params: ListenerParams,
+    time: Res<Time>,
+) {
+    let listener = listener_params.listener();
+
+    // Ease idle chatter down under grab/remove voices and back up afterwards.
+    // No prune here: voices started this frame have no instance until PostUpdate
+    let target = if audio_config.ducking && voice_manager.should_duck() {
+        audio_config.duck_level
+    } else {
+        1.0
+    };
+    let step = time.delta_secs() / DUCK_TIME;
+    voice_manager.duck += (target - voice_manager.duck).clamp(-step, step);
+
+    for (transform, current_audio) in query.iter() {
+        let Some(instance) = current_audio
+            .handle
+            .as_ref()
+            .and_then(|handle| audio_instances.get_mut(handle))
+        else {
+            continue;
+        };
+
+        let position = transform.translation.truncate();
+        let duck = current_audio
+            .kind
+            .map_or(1.0, |kind| duck_gain(kind, &voice_manager));
+        let amplitude = current_audio.volume * listener.gain(position, &audio_config) * duck;
+        instance.set_panning(
+            listener.panning(position, &audio_config),
+            AudioTween::default(),
+        );
+        instance.set_decibels(amplitude_to_db(amplitude), AudioTween::default());
+    }
+}
+
+/// Apply master volume, channel volumes and mutes to the audio channels
+pub fn channel_volume_system(
+    audio_config: Res<AudioConfig>,
+    voice_channel: Res<AudioChannel<VoiceChannel>>,
+    effects_channel: Res<AudioChannel<EffectsChannel>>,
+    music_channel: Res<AudioChannel<MusicChannel>>,
+) {
+    if !audio_config.is_changed() {
+        return;
+    }
+
+    let db = |volume, muted| amplitude_to_db(audio_config.channel_volume(volume, muted));
+    voice_channel.set_volume(db(audio_config.voice_volume, audio_config.voice_muted));
+    effects_channel.set_volume(db(audio_config.effects_volume, audio_config.effects_muted));
+    music_channel.set_volume(db(audio_config.music_volume, audio_config.music_muted));
+}
+
+/// Remember the global mute across restarts
+pub fn save_mute_system(audio_config: Res<AudioConfig>, mut saved: Local<Option<bool>>) {
+    // The first run only records the loaded state
+    if saved.is_some_and(|muted| muted != audio_config.muted) {
+        audio_config.save();
+    }
+    *saved = Some(audio_config.muted);
+}
+
+/// Loudness envelope of a clip: RMS per `ENVELOPE_STEP` window, scaled so the loudest window is 1.0
+fn loudness_envelope(source: &AudioSource) -> Vec<f32> {
+    let window = ((source.sound.sample_rate as f32 * ENVELOPE_STEP) as usize).max(1);
+    let mut envelope: Vec<f32> = source
+        .sound
+        .frames
+        .chunks(window)
+        .map(|chunk| {
+            let sum: f32 = chunk
+                .iter()
+                .map(|frame| {
+                    let mono = (frame.left + frame.right) * 0.5;
+                    mono * mono
+                })
+                .sum();
+            (sum / chunk.len() as f32).sqrt()
+        })
+        .collect();
+
+    let peak = envelope.iter().copied().fold(0.0, f32::max);
+    if peak > 0.0 {
+        for level in &mut envelope {
+            *level /= peak;
+        }
     }
+    envelope
 }
 
-/// Check for finished audio and close mouth
+/// Precompute the lip sync envelope of each voice once it has loaded
+pub fn voice_envelope_system(
+    mut audio_assets: ResMut<AudioAssets>,
+    audio_sources: Res<Assets<AudioSource>>,
+) {
+    let audio_assets = &mut *audio_assets;
+    audio_assets
+        .envelopes
+        .resize(audio_assets.voices.len(), Vec::new());
+
+    for (handle, envelope) in audio_assets.voices.iter().zip(&mut audio_assets.envelopes) {
+        if envelope.is_empty()
+            && let Some(source) = audio_sources.get(handle)
+        {
+            *envelope = loudness_envelope(source);
+        }
+    }
+}
+
+/// Switch a sprite to its open- or closed-mouth image, if it has one
+fn set_mouth(sprite_state: &mut SpriteState, sprites: &SpriteAssets, open: bool) {
+    if let Some(state) = sprites.states.get(sprite_state.current_index) {
+        let target = if open {
+            state.mouth_open
+        } else {
+            state.mouth_close
+        };
+        if let Some(index) = target {
+            sprite_state.current_index = index;
+        }
+    }
+}
+
+/// Flap the mouth with the voice's loudness, and close it when the voice ends
 pub fn mouth_animation_system(
     mut query: Query<(&mut CurrentAudio, &mut SpriteState), With<Speaki>>,
     audio_instances: Res<Assets<AudioInstance>>,
+    audio_assets: Res<AudioAssets>,
+    audio_config: Res<AudioConfig>,
     sprites: Res<SpriteAssets>,
 ) {
     for (mut current_audio, mut sprite_state) in query.iter_mut() {
-        if let Some(handle) = &current_audio.handle {
-            let should_close_mouth;
+        let Some(handle) = &current_audio.handle else {
+            continue;
+        };
 
-            // Check if audio is still playing
-            if let Some(instance) = audio_instances.get(handle) {
-                let state = instance.state();
-                should_close_mouth = state == PlaybackState::Stopped;
-            } else {
-                // Handle is invalid (audio finished and removed), close mouth
-                should_close_mouth = true;
-            }
+        // A missing instance means the audio finished and was removed
+        let state = audio_instances
+            .get(handle)
+            .map_or(PlaybackState::Stopped, |instance| instance.state());
 
-            if should_close_mouth {
+        match state {
+            PlaybackState::Stopped => {
                 // Audio finished, close mouth (40% chance like JS)
                 if rand::random::<f32>() < 0.4 {
-                    if let Some(state_node) = sprites.states.get(sprite_state.current_index) {
-                        if let Some(mouth_close_idx) = state_node.mouth_close {
-                            sprite_state.current_index = mouth_close_idx;
-                        }
-                    }
+                    set_mouth(&mut sprite_state, &sprites, false);
                 }
                 current_audio.handle = None;
+                current_audio.voice_index = None;
+                current_audio.kind = None;
+            }
+            PlaybackState::Playing { position } if audio_config.lip_sync => {
+                let level = current_audio
+                    .voice_index
+                    .and_then(|i| audio_assets.envelopes.get(i))
+                    .and_then(|envelope| envelope.get((position / ENVELOPE_STEP as f64) as usize));
+
+                // Open on loud syllables, close once it drops well below that
+                if let Some(&level) = level {
+                    if level > audio_config.lip_sync_threshold {
+                        set_mouth(&mut sprite_state, &sprites, true);
+                    } else if level < audio_config.lip_sync_threshold * LIP_SYNC_HYSTERESIS {
+                        set_mouth(&mut sprite_state, &sprites, false);
+                    }
+                }
             }
+            _ => {}
         }
     }
 }
@@ -96,13 +474,18 @@ pub fn bounce_voice_system(
     time: Res<Time>,
 ) {
     for event in bounce_events.read() {
-        // Play bounce voice
-        if !voice_groups.bounce.is_empty() {
-            let idx = voice_groups.bounce[rand::rng().random_range(0..voice_groups.bounce.len())];
+        // Hard slams get their own voice, and everything scales with the impact
+        let hard = if event.speed >= audio_config.hard_impact_speed {
+            voice_groups.pick(VoiceCategory::HardBounce)
+        } else {
+            None
+        };
+        if let Some(idx) = hard.or_else(|| voice_groups.pick(VoiceCategory::Bounce)) {
             voice_events.write(PlayVoiceEvent {
                 entity: Some(event.entity),
                 voice_index: idx,
-                volume: audio_config.bounce_volume,
+                volume: audio_config.bounce_volume * impact_gain(event.speed, &audio_config),
+                kind: VoiceKind::Bounce,
             });
         }
 
@@ -117,6 +500,33 @@ pub fn bounce_voice_system(
     }
 }
 
+/// Handle speaki-to-speaki collisions and play sound
+pub fn collision_voice_system(
+    mut collision_events: MessageReader<SpeakiCollisionEvent>,
+    mut voice_events: MessageWriter<PlayVoiceEvent>,
+    voice_groups: Res<VoiceGroups>,
+    audio_config: Res<AudioConfig>,
+) {
+    for event in collision_events.read() {
+        // The one landed on yelps; for side bumps either speaki can
+        let entity = if event.normal.y < -0.5 {
+            event.entity2
+        } else if event.normal.y > 0.5 || rand::random::<bool>() {
+            event.entity1
+        } else {
+            event.entity2
+        };
+        if let Some(idx) = voice_groups.pick(VoiceCategory::Collision) {
+            voice_events.write(PlayVoiceEvent {
+                entity: Some(entity),
+                voice_index: idx,
+                volume: audio_config.collision_volume * impact_gain(event.speed, &audio_config),
+                kind: VoiceKind::Collision,
+            });
+        }
+    }
+}
+
 /// Handle merge events and play sound
 pub fn merge_voice_system(
     mut merge_events: MessageReader<MergeSpeakiEvent>,
@@ -125,37 +535,124 @@ pub fn merge_voice_system(
     audio_config: Res<AudioConfig>,
 ) {
     for event in merge_events.read() {
-        // Play create voice for merge (sounds like a new bigger speaki)
-        if !voice_groups.create.is_empty() {
-            let idx = voice_groups.create[rand::rng().random_range(0..voice_groups.create.len())];
+        // Merge voice plays on the bigger speaki that's left
+        if let Some(idx) = voice_groups.pick(VoiceCategory::Merge) {
             voice_events.write(PlayVoiceEvent {
                 entity: Some(event.entity1), // Play on the remaining speaki
                 voice_index: idx,
                 volume: audio_config.create_volume,
+                kind: VoiceKind::Create,
             });
         }
     }
 }
 
+/// Handle shiny explosions and play sound on the exploding speaki
+pub fn explosion_voice_system(
+    mut explosion_events: MessageReader<ShinyExplosionEvent>,
+    mut voice_events: MessageWriter<PlayVoiceEvent>,
+    voice_groups: Res<VoiceGroups>,
+    audio_config: Res<AudioConfig>,
+) {
+    for event in explosion_events.read() {
+        if let Some(idx) = voice_groups.pick(VoiceCategory::Explosion) {
+            voice_events.write(PlayVoiceEvent {
+                entity: Some(event.entity),
+                voice_index: idx,
+                volume: audio_config.remove_volume,
+                kind: VoiceKind::Remove,
+            });
+        }
+    }
+}
+
+/// Play the thrown-far voice when a speaki is let go at speed
+pub fn thrown_voice_system(
+    mut released: RemovedComponents<Dragged>,
+    query: Query<&Velocity, With<Speaki>>,
+    mut voice_events: MessageWriter<PlayVoiceEvent>,
+    voice_groups: Res<VoiceGroups>,
+    audio_config: Res<AudioConfig>,
+) {
+    for entity in released.read() {
+        // Despawned while held, or just dropped
+        let Ok(vel) = query.get(entity) else {
+            continue;
+        };
+        if vel.x.hypot(vel.y) < audio_config.throw_voice_speed {
+            continue;
+        }
+
+        if let Some(idx) = voice_groups.pick(VoiceCategory::ThrownFar) {
+            voice_events.write(PlayVoiceEvent {
+                entity: Some(entity),
+                voice_index: idx,
+                volume: audio_config.grab_volume,
+                kind: VoiceKind::Grab,
+            });
+        }
+    }
+}
+
+/// Play the wake-up voice when a speaki that lay still for a while gets
+/// knocked about (grabbing it has its own voice)
+pub fn wake_up_voice_system(
+    query: Query<(Entity, &Velocity, Has<Dragged>), With<Speaki>>,
+    mut still_time: Local<HashMap<Entity, f32>>,
+    mut voice_events: MessageWriter<PlayVoiceEvent>,
+    voice_groups: Res<VoiceGroups>,
+    audio_config: Res<AudioConfig>,
+    time: Res<Time>,
+) {
+    let dt = time.delta_secs();
+    still_time.retain(|entity, _| query.contains(*entity));
+
+    for (entity, vel, dragged) in query.iter() {
+        let still = still_time.entry(entity).or_default();
+        if vel.x.hypot(vel.y) < WAKE_SPEED {
+            *still += dt;
+            continue;
+        }
+
+        if *still >= audio_config.wake_up_time
+            && !dragged
+            && let Some(idx) = voice_groups.pick(VoiceCategory::WakeUp)
+        {
+            voice_events.write(PlayVoiceEvent {
+                entity: Some(entity),
+                voice_index: idx,
+                volume: audio_config.idle_volume,
+                kind: VoiceKind::Idle,
+            });
+        }
+        *still = 0.0;
+    }
+}
+
+/// Speakis left alone long enough to talk to themselves
+type IdleSpeaki = (With<Speaki>, Without<Dragged>);
+
 /// Handle idle voice (random sounds when not interacting)
 pub fn idle_voice_system(
-    mut query: Query<
-        (&mut IdleVoiceTimer, &mut SpriteState, Entity),
-        (With<Speaki>, Without<Dragged>),
-    >,
+    mut query: Query<(&mut IdleVoiceTimer, &mut SpriteState, Entity), IdleSpeaki>,
     mut voice_events: MessageWriter<PlayVoiceEvent>,
     voice_groups: Res<VoiceGroups>,
     image_groups: Res<ImageGroups>,
     audio_config: Res<AudioConfig>,
     time: Res<Time>,
+    sim_speed: Res<SimulationSpeed>,
 ) {
     if audio_config.idle_frequency <= 0.0 {
         return;
     }
 
     let current_time = time.elapsed_secs();
+    // Push timers back by the time the simulation didn't run (or forward when fast)
+    let lag = time.delta_secs() * (1.0 - sim_speed.factor());
 
     for (mut timer, mut sprite_state, entity) in query.iter_mut() {
+        timer.last_idle_time += lag;
+
         // Calculate interval based on frequency
         // Original: (30000 / frequency - 29000) * cooldown + 3000 ms
         let interval = (30.0 / audio_config.idle_frequency - 29.0) * timer.idle_cooldown + 3.0;
@@ -164,13 +661,12 @@ pub fn idle_voice_system(
             // Play idle voice
             if rand::random::<f32>() > 0.8 {
                 // 20% chance for idle2
-                if !voice_groups.idle2.is_empty() {
-                    let idx =
-                        voice_groups.idle2[rand::rng().random_range(0..voice_groups.idle2.len())];
+                if let Some(idx) = voice_groups.pick(VoiceCategory::Idle2) {
                     voice_events.write(PlayVoiceEvent {
                         entity: Some(entity),
                         voice_index: idx,
                         volume: audio_config.idle_volume,
+                        kind: VoiceKind::Idle,
                     });
                 }
                 if !image_groups.idle2.is_empty() {
@@ -179,13 +675,12 @@ pub fn idle_voice_system(
                 }
             } else {
                 // 80% chance for idle
-                if !voice_groups.idle.is_empty() {
-                    let idx =
-                        voice_groups.idle[rand::rng().random_range(0..voice_groups.idle.len())];
+                if let Some(idx) = voice_groups.pick(VoiceCategory::Idle) {
                     voice_events.write(PlayVoiceEvent {
                         entity: Some(entity),
                         voice_index: idx,
                         volume: audio_config.idle_volume,
+                        kind: VoiceKind::Idle,
                     });
                 }
                 if !image_groups.idle.is_empty() {
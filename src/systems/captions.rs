@@ -0,0 +1,101 @@
+use bevy::prelude::*;
+use bevy_egui::EguiContexts;
+use bevy_egui::egui;
+
+use crate::components::*;
+use crate::resources::*;
+
+/// Seconds a caption takes to fade out after its voice ends
+const CAPTION_FADE: f32 = 0.8;
+/// Gap (pixels) between the top of a speaki and its speech bubble
+const CAPTION_MARGIN: f32 = 8.0;
+
+/// Give speakis a caption while they speak and start fading it when they stop
+pub fn caption_system(
+    mut commands: Commands,
+    mut query: Query<(Entity, &CurrentAudio, Option<&mut Caption>), With<Speaki>>,
+    audio_config: Res<AudioConfig>,
+    time: Res<Time>,
+) {
+    let current_time = time.elapsed_secs();
+
+    for (entity, current_audio, caption) in query.iter_mut() {
+        match (current_audio.voice_index, caption) {
+            // Speaking: show this voice's line (replacing any fading one)
+            (Some(voice), caption) if audio_config.captions => {
+                if let Some(mut caption) = caption {
+                    if caption.voice != voice || caption.ended.is_some() {
+                        caption.voice = voice;
+                        caption.ended = None;
+                    }
+                } else {
+                    commands
+                        .entity(entity)
+                        .insert(Caption { voice, ended: None });
+                }
+            }
+            // Voice over (or captions turned off): fade, then drop the caption
+            (_, Some(mut caption)) => match caption.ended {
+                None => caption.ended = Some(current_time),
+                Some(ended) if current_time - ended > CAPTION_FADE => {
+                    commands.entity(entity).remove::<Caption>();
+                }
+                Some(_) => {}
+            },
+            _ => {}
+        }
+    }
+}
+
+/// Draw captions as speech bubbles above their speakis
+pub fn caption_ui_system(
+    mut contexts: EguiContexts,
+    query: Query<(Entity, &Caption, &GlobalTransform, &SpeakiSize), With<Speaki>>,
+    camera_q: Query<(&Camera, &GlobalTransform)>,
+    audio_assets: Res<AudioAssets>,
+    caption_table: Res<CaptionTable>,
+    audio_config: Res<AudioConfig>,
+    time: Res<Time>,
+) -> Result {
+    let ctx = contexts.ctx_mut()?;
+
+    let Ok((camera, camera_transform)) = camera_q.single() else {
+        return Ok(());
+    };
+
+    let current_time = time.elapsed_secs();
+
+    for (entity, caption, transform, size) in query.iter() {
+        let Some(text) = audio_assets
+            .names
+            .get(caption.voice)
+            .and_then(|name| caption_table.get(name, audio_config.caption_language))
+        else {
+            continue;
+        };
+
+        // Bubbles stay upright above the speaki whatever its rotation
+        let top = transform.translation() + Vec3::Y * (size.0 / 2.0 + CAPTION_MARGIN);
+        let Ok(pos) = camera.world_to_viewport(camera_transform, top) else {
+            continue;
+        };
+
+        let opacity = caption.ended.map_or(1.0, |ended| {
+            (1.0 - (current_time - ended) / CAPTION_FADE).clamp(0.0, 1.0)
+        });
+
+        egui::Area::new(egui::Id::new(("speaki_caption", entity)))
+            .fixed_pos(egui::pos2(pos.x, pos.y))
+            .pivot(egui::Align2::CENTER_BOTTOM)
+            .order(egui::Order::Background)
+            .interactable(false)
+            .show(ctx, |ui| {
+                ui.multiply_opacity(opacity);
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.label(text);
+                });
+            });
+    }
+
+    Ok(())
+}
@@ -0,0 +1,526 @@
+use std::io::{BufRead, BufReader, Read, Write};
+use std::sync::Mutex;
+use std::sync::mpsc::{self, Receiver, Sender};
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+use crate::components::*;
+use crate::events::*;
+use crate::resources::*;
+
+/// Default TCP address used when Unix sockets are not available
+#[cfg(not(target_os = "linux"))]
+const DEFAULT_CONTROL_ADDR: &str = "127.0.0.1:7878";
+
+/// A command sent by a control client (one JSON object per line)
+///
+/// Example: `{"cmd": "spawn", "x": 0, "y": 200}`
+#[derive(Deserialize)]
+#[serde(tag = "cmd", rename_all = "snake_case")]
+pub enum ControlCommand {
+    Spawn {
+        x: Option<f32>,
+        y: Option<f32>,
+        #[serde(default)]
+        vx: f32,
+        #[serde(default)]
+        vy: f32,
+    },
+    Despawn {
+        id: u64,
+    },
+    Clear,
+    Voice {
+        index: usize,
+        id: Option<u64>,
+        #[serde(default = "default_volume")]
+        volume: f32,
+    },
+    Set(ConfigPatch),
+    Count,
+    List,
+}
+
+fn default_volume() -> f32 {
+    1.0
+}
+
+/// Partial config update, only the given fields are changed
+///
+/// Values are clamped to the ranges of the settings sliders, unknown fields are an error.
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+pub struct ConfigPatch {
+    pub gravity: Option<f32>,
+    pub bounce: Option<f32>,
+    pub friction: Option<f32>,
+    pub rotation_speed: Option<f32>,
+    pub collision_enabled: Option<bool>,
+    pub throwing_power: Option<f32>,
+    pub speaki_size: Option<f32>,
+    pub click_to_add: Option<bool>,
+    pub eye_blink_enabled: Option<bool>,
+    pub master_volume: Option<f32>,
+    pub idle_frequency: Option<f32>,
+    pub shiny_enabled: Option<bool>,
+    pub shiny_chance: Option<f32>,
+    pub merge_enabled: Option<bool>,
+}
+
+/// A parsed command waiting for the game loop, with a channel for the reply
+pub struct ControlRequest {
+    pub command: ControlCommand,
+    pub reply: Sender<Value>,
+}
+
+/// Receiving end of the control server (requests from client threads)
+#[derive(Resource)]
+pub struct ControlServer {
+    requests: Mutex<Receiver<ControlRequest>>,
+}
+
+/// Start the control server on a background thread
+pub fn start_control_server(mut commands: Commands) {
+    let (tx, rx) = mpsc::channel();
+
+    std::thread::spawn(move || {
+        if let Err(e) = listen(tx) {
+            warn!("Control server stopped: {}", e);
+        }
+    });
+
+    commands.insert_resource(ControlServer {
+        requests: Mutex::new(rx),
+    });
+}
+
+/// Socket path: SPEAKI_CONTROL_SOCKET, or speaki-box.sock in the runtime dir
+#[cfg(target_os = "linux")]
+fn socket_path() -> std::path::PathBuf {
+    if let Ok(path) = std::env::var("SPEAKI_CONTROL_SOCKET") {
+        return path.into();
+    }
+
+    let dir = std::env::var("XDG_RUNTIME_DIR").unwrap_or_else(|_| "/tmp".to_string());
+    std::path::Path::new(&dir).join("speaki-box.sock")
+}
+
+#[cfg(target_os = "linux")]
+fn listen(tx: Sender<ControlRequest>) -> std::io::Result<()> {
+    use std::os::unix::net::UnixListener;
+
+    let path = socket_path();
+    // Remove a stale socket left behind by a previous run
+    let _ = std::fs::remove_file(&path);
+
+    let listener = UnixListener::bind(&path)?;
+    info!("Control server listening on {}", path.display());
+
+    for stream in listener.incoming() {
+        // A failed connection shouldn't take the whole server down
+        let (stream, reader) = match stream.and_then(|s| s.try_clone().map(|r| (s, r))) {
+            Ok(pair) => pair,
+            Err(e) => {
+                warn!("Control connection failed: {}", e);
+                continue;
+            }
+        };
+        let tx = tx.clone();
+        std::thread::spawn(move || handle_client(reader, stream, tx));
+    }
+
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn listen(tx: Sender<ControlRequest>) -> std::io::Result<()> {
+    use std::net::TcpListener;
+
+    let addr =
+        std::env::var("SPEAKI_CONTROL_ADDR").unwrap_or_else(|_| DEFAULT_CONTROL_ADDR.to_string());
+
+    let listener = TcpListener::bind(&addr)?;
+    info!("Control server listening on {}", addr);
+
+    for stream in listener.incoming() {
+        // A failed connection shouldn't take the whole server down
+        let (stream, reader) = match stream.and_then(|s| s.try_clone().map(|r| (s, r))) {
+            Ok(pair) => pair,
+            Err(e) => {
+                warn!("Control connection failed: {}", e);
+                continue;
+            }
+        };
+        // Only accept local connections
+        if !stream.peer_addr().is_ok_and(|addr| addr.ip().is_loopback()) {
+            continue;
+        }
+        let tx = tx.clone();
+        std::thread::spawn(move || handle_client(reader, stream, tx));
+    }
+
+    Ok(())
+}
+
+/// Read line-delimited JSON commands and write one JSON reply per line
+fn handle_client(reader: impl Read, mut writer: impl Write, tx: Sender<ControlRequest>) {
+    for line in BufReader::new(reader).lines() {
+        let Ok(line) = line else {
+            return;
+        };
+
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let response = match serde_json::from_str::<ControlCommand>(&line) {
+            Ok(command) => {
+                let (reply_tx, reply_rx) = mpsc::channel();
+                if tx
+                    .send(ControlRequest {
+                        command,
+                        reply: reply_tx,
+                    })
+                    .is_err()
+                {
+                    // Game loop is gone
+                    return;
+                }
+                reply_rx
+                    .recv()
+                    .unwrap_or_else(|_| error_response("command dropped"))
+            }
+            Err(e) => error_response(&e.to_string()),
+        };
+
+        if writeln!(writer, "{}", response).is_err() {
+            return;
+        }
+    }
+}
+
+fn error_response(message: &str) -> Value {
+    json!({ "ok": false, "error": message })
+}
+
+/// Keep a spawn inside the box and under the throw speed cap, rejecting NaN/infinite values
+fn sanitized_spawn(
+    position: Vec2,
+    velocity: Vec2,
+    bounds: Rect,
+    max_speed: f32,
+) -> Option<(Vec2, Vec2)> {
+    if !position.is_finite() || !velocity.is_finite() {
+        return None;
+    }
+    Some((
+        position.clamp(bounds.min, bounds.max),
+        velocity.clamp_length_max(max_speed),
+    ))
+}
+
+/// Settings the `set` command can change
+#[derive(SystemParam)]
+pub struct ControlConfigs<'w> {
+    physics: ResMut<'w, PhysicsConfig>,
+    game: ResMut<'w, GameConfig>,
+    audio: ResMut<'w, AudioConfig>,
+    shiny: ResMut<'w, ShinyConfig>,
+    merge: ResMut<'w, MergeConfig>,
+}
+
+/// Apply pending control commands inside the game loop
+pub fn control_command_system(
+    server: Res<ControlServer>,
+    window: Single<&Window>,
+    border: Res<BorderConfig>,
+    speaki_query: Query<(Entity, &Transform, &SpeakiSize, Has<Shiny>), With<Speaki>>,
+    (mut spawn_events, mut despawn_events, mut voice_events): (
+        MessageWriter<SpawnSpeakiEvent>,
+        MessageWriter<DespawnSpeakiEvent>,
+        MessageWriter<PlayVoiceEvent>,
+    ),
+    mut configs: ControlConfigs,
+) {
+    let Ok(requests) = server.requests.lock() else {
+        return;
+    };
+
+    while let Ok(request) = requests.try_recv() {
+        let response = match request.command {
+            ControlCommand::Spawn { x, y, vx, vy } => {
+                let half_width = window.width() / 2.0;
+                let half_height = window.height() / 2.0;
+
+                // Random position in top 30% of screen unless given
+                let x = x.unwrap_or_else(|| (rand::random::<f32>() - 0.5) * 2.0 * half_width);
+                let y = y.unwrap_or_else(|| {
+                    half_height * 0.4 + rand::random::<f32>() * half_height * 0.6
+                });
+
+                let bounds = border.bounds(window.width(), window.height());
+                match sanitized_spawn(
+                    Vec2::new(x, y),
+                    Vec2::new(vx, vy),
+                    bounds,
+                    configs.physics.max_throw_speed,
+                ) {
+                    Some((position, velocity)) => {
+                        spawn_events.write(SpawnSpeakiEvent { position, velocity });
+                        json!({ "ok": true })
+                    }
+                    None => error_response("position and velocity must be finite"),
+                }
+            }
+            ControlCommand::Despawn { id } => {
+                match Entity::try_from_bits(id).filter(|e| speaki_query.contains(*e)) {
+                    Some(entity) => {
+                        despawn_events.write(DespawnSpeakiEvent { entity });
+                        json!({ "ok": true })
+                    }
+                    None => error_response("no such speaki"),
+                }
+            }
+            ControlCommand::Clear => {
+                let mut removed = 0;
+                for (entity, ..) in speaki_query.iter() {
+                    despawn_events.write(DespawnSpeakiEvent { entity });
+                    removed += 1;
+                }
+                json!({ "ok": true, "removed": removed })
+            }
+            ControlCommand::Voice { index, id, volume } => {
+                let entity = id
+                    .and_then(Entity::try_from_bits)
+                    .filter(|e| speaki_query.contains(*e));
+                voice_events.write(PlayVoiceEvent {
+                    entity,
+                    voice_index: index,
+                    volume: volume.clamp(0.0, 1.0),
+                    kind: VoiceKind::Manual,
+                });
+                json!({ "ok": true })
+            }
+            ControlCommand::Set(patch) => {
+                apply_patch(
+                    &patch,
+                    &mut configs.physics,
+                    &mut configs.game,
+                    &mut configs.audio,
+                    &mut configs.shiny,
+                    &mut configs.merge,
+                );
+                json!({ "ok": true })
+            }
+            ControlCommand::Count => {
+                json!({ "ok": true, "count": speaki_query.iter().count() })
+            }
+            ControlCommand::List => {
+                let speakis: Vec<Value> = speaki_query
+                    .iter()
+                    .map(|(entity, transform, size, shiny)| {
+                        json!({
+                            "id": entity.to_bits(),
+                            "x": transform.translation.x,
+                            "y": transform.translation.y,
+                            "size": size.0,
+                            "shiny": shiny,
+                        })
+                    })
+                    .collect();
+                json!({ "ok": true, "speakis": speakis })
+            }
+        };
+
+        // Client may have disconnected, nothing to do then
+        let _ = request.reply.send(response);
+    }
+}
+
+/// Apply a config patch, clamping each value to its settings slider range
+fn apply_patch(
+    patch: &ConfigPatch,
+    physics_config: &mut PhysicsConfig,
+    game_config: &mut GameConfig,
+    audio_config: &mut AudioConfig,
+    shiny_config: &mut ShinyConfig,
+    merge_config: &mut MergeConfig,
+) {
+    if let Some(v) = patch.gravity {
+        physics_config.gravity = v.clamp(0.0, 2.0);
+    }
+    if let Some(v) = patch.bounce {
+        physics_config.bounce = v.clamp(0.0, 1.0);
+    }
+    if let Some(v) = patch.friction {
+        physics_config.friction = v.clamp(0.0, 1.0);
+    }
+    if let Some(v) = patch.rotation_speed {
+        physics_config.rotation_speed = v.clamp(0.0, 1.0);
+    }
+    if let Some(v) = patch.collision_enabled {
+        physics_config.collision_enabled = v;
+    }
+    if let Some(v) = patch.throwing_power {
+        physics_config.cursor_throwing_power = v.clamp(0.0, 3.0);
+    }
+    if let Some(v) = patch.speaki_size {
+        game_config.speaki_size = v.clamp(50.0, 400.0);
+    }
+    if let Some(v) = patch.click_to_add {
+        game_config.click_to_add = v;
+    }
+    if let Some(v) = patch.eye_blink_enabled {
+        game_config.eye_blink_enabled = v;
+    }
+    if let Some(v) = patch.master_volume {
+        audio_config.master_volume = v.clamp(0.0, 1.0);
+    }
+    if let Some(v) = patch.idle_frequency {
+        audio_config.idle_frequency = v.clamp(0.0, 1.0);
+    }
+    if let Some(v) = patch.shiny_enabled {
+        shiny_config.enabled = v;
+    }
+    if let Some(v) = patch.shiny_chance {
+        shiny_config.spawn_chance = v.clamp(0.0, 1.0);
+    }
+    if let Some(v) = patch.merge_enabled {
+        merge_config.enabled = v;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(line: &str) -> Result<ControlCommand, serde_json::Error> {
+        serde_json::from_str(line)
+    }
+
+    fn patched(patch: &str) -> (PhysicsConfig, GameConfig, AudioConfig, ShinyConfig) {
+        let Ok(ControlCommand::Set(patch)) = parse(patch) else {
+            panic!("not a set command: {patch}");
+        };
+        let mut physics_config = PhysicsConfig::default();
+        let mut game_config = GameConfig::default();
+        let mut audio_config = AudioConfig::default();
+        let mut shiny_config = ShinyConfig::default();
+        apply_patch(
+            &patch,
+            &mut physics_config,
+            &mut game_config,
+            &mut audio_config,
+            &mut shiny_config,
+            &mut MergeConfig::default(),
+        );
+        (physics_config, game_config, audio_config, shiny_config)
+    }
+
+    #[test]
+    fn spawn_defaults_to_random_position_and_no_velocity() {
+        let Ok(ControlCommand::Spawn { x, y, vx, vy }) = parse(r#"{"cmd":"spawn"}"#) else {
+            panic!("not a spawn command");
+        };
+        assert_eq!((x, y), (None, None));
+        assert_eq!((vx, vy), (0.0, 0.0));
+
+        let Ok(ControlCommand::Spawn { x, y, .. }) = parse(r#"{"cmd":"spawn","x":10,"y":-5}"#)
+        else {
+            panic!("not a spawn command");
+        };
+        assert_eq!((x, y), (Some(10.0), Some(-5.0)));
+    }
+
+    #[test]
+    fn spawn_is_clamped_to_the_box_and_speed_cap() {
+        let bounds = Rect::new(-100.0, -50.0, 100.0, 50.0);
+
+        let (position, velocity) =
+            sanitized_spawn(Vec2::new(500.0, -80.0), Vec2::new(300.0, 400.0), bounds, 80.0)
+                .expect("finite spawn is accepted");
+        assert_eq!(position, Vec2::new(100.0, -50.0));
+        assert!((velocity.length() - 80.0).abs() < 1e-3);
+        assert!(velocity.x > 0.0 && velocity.y > 0.0);
+
+        let (position, velocity) =
+            sanitized_spawn(Vec2::new(10.0, 20.0), Vec2::new(3.0, -4.0), bounds, 80.0)
+                .expect("finite spawn is accepted");
+        assert_eq!((position, velocity), (Vec2::new(10.0, 20.0), Vec2::new(3.0, -4.0)));
+    }
+
+    #[test]
+    fn non_finite_spawn_is_rejected() {
+        let bounds = Rect::new(-100.0, -50.0, 100.0, 50.0);
+        assert!(sanitized_spawn(Vec2::new(f32::NAN, 0.0), Vec2::ZERO, bounds, 80.0).is_none());
+        assert!(sanitized_spawn(Vec2::ZERO, Vec2::new(0.0, f32::INFINITY), bounds, 80.0).is_none());
+
+        // 1e39 overflows f32 while parsing
+        let Ok(ControlCommand::Spawn { vx, .. }) = parse(r#"{"cmd":"spawn","vx":1e39}"#) else {
+            panic!("not a spawn command");
+        };
+        assert!(sanitized_spawn(Vec2::ZERO, Vec2::new(vx, 0.0), bounds, 80.0).is_none());
+    }
+
+    #[test]
+    fn voice_defaults_to_full_volume() {
+        let Ok(ControlCommand::Voice { index, id, volume }) =
+            parse(r#"{"cmd":"voice","index":3}"#)
+        else {
+            panic!("not a voice command");
+        };
+        assert_eq!((index, id, volume), (3, None, 1.0));
+    }
+
+    #[test]
+    fn commands_without_arguments() {
+        assert!(matches!(parse(r#"{"cmd":"clear"}"#), Ok(ControlCommand::Clear)));
+        assert!(matches!(parse(r#"{"cmd":"count"}"#), Ok(ControlCommand::Count)));
+        assert!(matches!(parse(r#"{"cmd":"list"}"#), Ok(ControlCommand::List)));
+    }
+
+    #[test]
+    fn bad_commands_are_rejected() {
+        assert!(parse(r#"{"cmd":"explode"}"#).is_err());
+        assert!(parse(r#"{"cmd":"despawn"}"#).is_err());
+        assert!(parse(r#"{"x":1}"#).is_err());
+        assert!(parse("not json").is_err());
+    }
+
+    #[test]
+    fn misspelled_config_field_is_an_error() {
+        assert!(parse(r#"{"cmd":"set","gravty":1.0}"#).is_err());
+    }
+
+    #[test]
+    fn patch_changes_only_given_fields() {
+        let (physics_config, game_config, ..) = patched(r#"{"cmd":"set","gravity":1.5}"#);
+        assert_eq!(physics_config.gravity, 1.5);
+        assert_eq!(physics_config.bounce, PhysicsConfig::default().bounce);
+        assert_eq!(game_config.speaki_size, GameConfig::default().speaki_size);
+    }
+
+    #[test]
+    fn patch_is_clamped_to_slider_ranges() {
+        let (physics_config, game_config, audio_config, shiny_config) = patched(
+            r#"{"cmd":"set","gravity":-1,"bounce":50,"friction":2,"rotation_speed":-3,
+                "throwing_power":1e39,"speaki_size":0,"master_volume":5,
+                "idle_frequency":-1,"shiny_chance":2}"#,
+        );
+        assert_eq!(physics_config.gravity, 0.0);
+        assert_eq!(physics_config.bounce, 1.0);
+        assert_eq!(physics_config.friction, 1.0);
+        assert_eq!(physics_config.rotation_speed, 0.0);
+        assert_eq!(physics_config.cursor_throwing_power, 3.0);
+        assert_eq!(game_config.speaki_size, 50.0);
+        assert_eq!(audio_config.master_volume, 1.0);
+        assert_eq!(audio_config.idle_frequency, 0.0);
+        assert_eq!(shiny_config.spawn_chance, 1.0);
+
+        let (_, game_config, ..) = patched(r#"{"cmd":"set","speaki_size":-100}"#);
+        assert_eq!(game_config.speaki_size, 50.0);
+    }
+}
@@ -0,0 +1,350 @@
+use bevy::asset::uuid::Uuid;
+use bevy::camera::NormalizedRenderTarget;
+use bevy::ecs::system::SystemParam;
+use bevy::input::gamepad::{GamepadRumbleIntensity, GamepadRumbleRequest};
+use bevy::picking::hover::HoverMap;
+use bevy::picking::pointer::{Location, PointerId, PointerLocation};
+use bevy::prelude::*;
+use bevy::window::{PrimaryWindow, WindowRef};
+use std::time::Duration;
+
+use crate::components::*;
+use crate::events::*;
+use crate::resources::*;
+use crate::systems::{THROW_SPIN_THRESHOLD, Voices, grab_offset, spring_drag};
+
+/// Picking pointer for the gamepad's virtual cursor
+const GAMEPAD_POINTER: PointerId =
+    PointerId::Custom(Uuid::from_u128(0x5be4_6a1e_9a31_4d0c_8b7e_2f11_c0de_0031));
+
+/// Sticks report small values at rest
+const STICK_DEADZONE: f32 = 0.15;
+
+/// Ring drawn at the virtual cursor (drawn above speakis, below remote cursors)
+const CURSOR_RADIUS: f32 = 14.0;
+const CURSOR_Z: f32 = 450.0;
+
+/// Spawn the picking pointer and the (hidden) cursor ring
+pub fn setup_gamepad_cursor(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    commands.spawn((GAMEPAD_POINTER, PointerLocation::default()));
+
+    commands.spawn((
+        GamepadCursorMarker,
+        Mesh2d(meshes.add(Annulus::new(CURSOR_RADIUS * 0.7, CURSOR_RADIUS))),
+        MeshMaterial2d(materials.add(ColorMaterial::from_color(Color::srgba(1.0, 1.0, 1.0, 0.8)))),
+        Transform::from_xyz(0.0, 0.0, CURSOR_Z),
+        Visibility::Hidden,
+    ));
+}
+
+fn deadzone(stick: Vec2) -> Vec2 {
+    if stick.length() < STICK_DEADZONE {
+        Vec2::ZERO
+    } else {
+        stick
+    }
+}
+
+/// The cursor ring (never a speaki, so it can share `Transform` access with them)
+type CursorMarker = (With<GamepadCursorMarker>, Without<Speaki>);
+
+/// Window, picking pointer and ring marker the virtual cursor drives
+#[derive(SystemParam)]
+pub struct GamepadPointer<'w, 's> {
+    window: Single<'w, 's, (Entity, &'static Window), With<PrimaryWindow>>,
+    camera_q: Query<'w, 's, (&'static Camera, &'static GlobalTransform)>,
+    pointer_q: Query<'w, 's, (&'static PointerId, &'static mut PointerLocation)>,
+    marker_q: Query<'w, 's, (&'static mut Transform, &'static mut Visibility), CursorMarker>,
+    hover_map: Res<'w, HoverMap>,
+}
+
+/// Speaki state the virtual cursor grabs, drags and throws
+type GamepadTarget = (
+    &'static mut Transform,
+    &'static mut Velocity,
+    &'static mut RotationState,
+    &'static mut IdleVoiceTimer,
+    &'static SpeakiSize,
+    Has<Dragged>,
+);
+
+/// What the gamepad buttons do to the box
+#[derive(SystemParam)]
+pub struct GamepadActions<'w, 's> {
+    commands: Commands<'w, 's>,
+    z_order: ResMut<'w, ZOrder>,
+    spawn_events: MessageWriter<'w, SpawnSpeakiEvent>,
+    despawn_events: MessageWriter<'w, DespawnSpeakiEvent>,
+    voices: Voices<'w>,
+}
+
+/// Move the virtual cursor, grab/throw with South, spawn/remove with the shoulder buttons
+pub fn gamepad_cursor_system(
+    gamepads: Query<&Gamepad>,
+    pointer: GamepadPointer,
+    mut speaki_query: Query<GamepadTarget, With<Speaki>>,
+    mut cursor: ResMut<GamepadCursor>,
+    actions: GamepadActions,
+    (gamepad_config, physics, config): (Res<GamepadConfig>, Res<PhysicsConfig>, Res<GameConfig>),
+    time: Res<Time>,
+) {
+    let GamepadPointer {
+        window,
+        camera_q,
+        mut pointer_q,
+        mut marker_q,
+        hover_map,
+    } = pointer;
+    let GamepadActions {
+        mut commands,
+        mut z_order,
+        mut spawn_events,
+        mut despawn_events,
+        mut voices,
+    } = actions;
+
+    let (window_entity, window) = *window;
+    let gamepad = gamepads.iter().next().filter(|_| gamepad_config.enabled);
+
+    let Some(gamepad) = gamepad else {
+        // Gamepad gone or disabled: drop whatever it held and hide the cursor
+        if let Some(entity) = cursor.held_entity.take() {
+            commands.entity(entity).try_remove::<Dragged>();
+        }
+        if cursor.active {
+            cursor.active = false;
+            for (_, mut visibility) in marker_q.iter_mut() {
+                *visibility = Visibility::Hidden;
+            }
+            for (id, mut location) in pointer_q.iter_mut() {
+                if *id == GAMEPAD_POINTER {
+                    location.location = None;
+                }
+            }
+        }
+        return;
+    };
+
+    let stick = deadzone(gamepad.left_stick());
+    if !cursor.active && stick == Vec2::ZERO && gamepad.get_just_pressed().next().is_none() {
+        return;
+    }
+    cursor.active = true;
+
+    let current_time = time.elapsed_secs();
+
+    // Move the cursor, keeping it inside the window
+    let half_size = window.size() / 2.0;
+    cursor.position = (cursor.position + stick * gamepad_config.cursor_speed * time.delta_secs())
+        .clamp(-half_size, half_size);
+    let cursor_pos = cursor.position;
+
+    for (mut transform, mut visibility) in marker_q.iter_mut() {
+        transform.translation.x = cursor_pos.x;
+        transform.translation.y = cursor_pos.y;
+        *visibility = Visibility::Visible;
+    }
+
+    // Feed the cursor into picking so hover effects and hit-testing match the mouse
+    if let Ok((camera, camera_transform)) = camera_q.single()
+        && let Ok(viewport_pos) = camera.world_to_viewport(camera_transform, cursor_pos.extend(0.0))
+        && let Some(target) = WindowRef::Primary.normalize(Some(window_entity))
+    {
+        for (id, mut location) in pointer_q.iter_mut() {
+            if *id == GAMEPAD_POINTER {
+                location.location = Some(Location {
+                    target: NormalizedRenderTarget::Window(target),
+                    position: viewport_pos,
+                });
+            }
+        }
+    }
+
+    // Topmost speaki under the cursor
+    let hovered = hover_map.get(&GAMEPAD_POINTER).and_then(|hits| {
+        hits.iter()
+            .filter(|(entity, _)| speaki_query.contains(**entity))
+            .min_by(|a, b| a.1.depth.total_cmp(&b.1.depth))
+            .map(|(entity, _)| *entity)
+    });
+
+    cursor.history.push(current_time, cursor_pos);
+
+    // Held speaki follows the cursor
+    if let Some(entity) = cursor.held_entity
+        && let Ok((mut transform, mut vel, mut rot, _, size, _)) = speaki_query.get_mut(entity)
+    {
+        if physics.spring_drag {
+            spring_drag(
+                &transform,
+                &mut vel,
+                &mut rot,
+                size.0,
+                cursor.grab_offset,
+                cursor_pos,
+                &physics,
+            );
+        } else {
+            transform.translation.x = cursor_pos.x;
+            transform.translation.y = cursor_pos.y;
+            vel.x = 0.0;
+            vel.y = 0.0;
+        }
+    }
+
+    // Grab
+    if gamepad.just_pressed(GamepadButton::South)
+        && cursor.held_entity.is_none()
+        && let Some(entity) = hovered
+        && let Ok((mut transform, _, _, _, _, held)) = speaki_query.get_mut(entity)
+        && !held
+    {
+        cursor.held_entity = Some(entity);
+        cursor.grab_offset = grab_offset(&transform, cursor_pos);
+        cursor.history.start(current_time, cursor_pos);
+
+        transform.translation.z = z_order.next();
+        commands.entity(entity).insert(Dragged);
+
+        if let Some(idx) = voices.groups.pick(VoiceCategory::Drag) {
+            voices.events.write(PlayVoiceEvent {
+                entity: Some(entity),
+                voice_index: idx,
+                volume: voices.config.grab_volume,
+                kind: VoiceKind::Grab,
+            });
+        }
+    }
+
+    // Release and throw with the cursor's recent movement
+    if gamepad.just_released(GamepadButton::South)
+        && let Some(entity) = cursor.held_entity.take()
+    {
+        let (throw_vel, spin) = physics.throw(&cursor.history, current_time);
+
+        if let Ok((_, mut vel, mut rot, mut idle_timer, ..)) = speaki_query.get_mut(entity) {
+            vel.x = throw_vel.x;
+            vel.y = throw_vel.y;
+            if spin.abs() > THROW_SPIN_THRESHOLD {
+                rot.speed = spin;
+            }
+            idle_timer.last_idle_time = current_time;
+            commands.entity(entity).remove::<Dragged>();
+        }
+    }
+
+    // Right shoulder: spawn at the cursor
+    if gamepad.just_pressed(GamepadButton::RightTrigger) && config.click_to_add {
+        spawn_events.write(SpawnSpeakiEvent {
+            position: cursor_pos,
+            velocity: Vec2::ZERO,
+        });
+
+        if let Some(idx) = voices.groups.pick(VoiceCategory::Create) {
+            voices.events.write(PlayVoiceEvent {
+                entity: None,
+                voice_index: idx,
+                volume: voices.config.create_volume,
+                kind: VoiceKind::Create,
+            });
+        }
+    }
+
+    // Left shoulder: remove the speaki under the cursor
+    if gamepad.just_pressed(GamepadButton::LeftTrigger)
+        && let Some(entity) = hovered
+        && cursor.held_entity != Some(entity)
+    {
+        despawn_events.write(DespawnSpeakiEvent { entity });
+
+        if let Some(idx) = voices.groups.pick(VoiceCategory::Remove) {
+            voices.events.write(PlayVoiceEvent {
+                entity: Some(entity),
+                voice_index: idx,
+                volume: voices.config.remove_volume,
+                kind: VoiceKind::Remove,
+            });
+        }
+    }
+}
+
+/// Tilt gravity with the right stick or the analog triggers
+pub fn gamepad_tilt_system(
+    gamepads: Query<&Gamepad>,
+    gamepad_config: Res<GamepadConfig>,
+    mut physics: ResMut<PhysicsConfig>,
+) {
+    let tilt = gamepads
+        .iter()
+        .next()
+        .filter(|_| gamepad_config.enabled && gamepad_config.tilt_enabled)
+        .map(|gamepad| {
+            let stick = deadzone(gamepad.right_stick()).x;
+            let triggers = gamepad.get(GamepadButton::RightTrigger2).unwrap_or(0.0)
+                - gamepad.get(GamepadButton::LeftTrigger2).unwrap_or(0.0);
+            (stick + triggers).clamp(-1.0, 1.0)
+        })
+        .unwrap_or(0.0);
+
+    // Positive tilt rolls gravity towards the right
+    let direction = Vec2::from_angle(tilt * gamepad_config.max_tilt).rotate(Vec2::NEG_Y);
+
+    // Only write on change so other systems don't see a constantly modified config
+    if physics.gravity_direction.distance_squared(direction) > 1e-6 {
+        physics.gravity_direction = direction;
+    }
+}
+
+/// Rumble on heavy wall bounces and shiny explosions
+pub fn gamepad_rumble_system(
+    gamepads: Query<Entity, With<Gamepad>>,
+    mut bounce_events: MessageReader<WallBounceEvent>,
+    mut explosion_events: MessageReader<ShinyExplosionEvent>,
+    gamepad_config: Res<GamepadConfig>,
+    mut rumble_requests: MessageWriter<GamepadRumbleRequest>,
+) {
+    // Impact speed into the wall, not the speed left after the bounce
+    let mut hardest_bounce = 0.0_f32;
+    let mut bounce_normal = Vec2::ZERO;
+    for event in bounce_events.read() {
+        if event.speed > hardest_bounce {
+            hardest_bounce = event.speed;
+            bounce_normal = event.normal;
+        }
+    }
+    let exploded = explosion_events.read().count() > 0;
+
+    if !gamepad_config.enabled || !gamepad_config.rumble_enabled {
+        return;
+    }
+
+    let rumble = if exploded {
+        Some((Duration::from_millis(300), GamepadRumbleIntensity::MAX))
+    } else if hardest_bounce > gamepad_config.rumble_threshold {
+        // Twice the threshold (or more) rumbles at full strength
+        let strength = (hardest_bounce / (2.0 * gamepad_config.rumble_threshold)).min(1.0);
+        // Floor and ceiling slams use the heavy motor, side walls the light one
+        let intensity = if bounce_normal.y != 0.0 {
+            GamepadRumbleIntensity::strong_motor(strength)
+        } else {
+            GamepadRumbleIntensity::weak_motor(strength)
+        };
+        Some((Duration::from_millis(120), intensity))
+    } else {
+        None
+    };
+
+    if let Some((duration, intensity)) = rumble {
+        for gamepad in gamepads.iter() {
+            rumble_requests.write(GamepadRumbleRequest::Add {
+                duration,
+                intensity,
+                gamepad,
+            });
+        }
+    }
+}
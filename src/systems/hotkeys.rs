@@ -0,0 +1,157 @@
+use bevy::prelude::*;
+use bevy::render::view::screenshot::{Screenshot, save_to_disk};
+
+use crate::components::*;
+use crate::events::*;
+use crate::resources::*;
+use crate::systems::{SettingsOpen, Voices};
+
+/// Toggles bound in the key map: settings, title bar, merge, mute, speed, inspect, screenshot
+pub fn hotkey_toggle_system(
+    mut commands: Commands,
+    (keyboard, key_bindings): (Res<ButtonInput<KeyCode>>, Res<KeyBindings>),
+    mut settings_open: ResMut<SettingsOpen>,
+    (mut game_config, mut merge_config, mut audio_config): (
+        ResMut<GameConfig>,
+        ResMut<MergeConfig>,
+        ResMut<AudioConfig>,
+    ),
+    mut sim_speed: ResMut<SimulationSpeed>,
+    mut inspector: ResMut<Inspector>,
+) {
+    if key_bindings.just_pressed(KeyAction::ToggleSettings, &keyboard) {
+        settings_open.0 = !settings_open.0;
+    }
+    if key_bindings.just_pressed(KeyAction::ToggleTitlebar, &keyboard) {
+        game_config.window_decorations = !game_config.window_decorations;
+    }
+    if key_bindings.just_pressed(KeyAction::ToggleMerge, &keyboard) {
+        merge_config.enabled = !merge_config.enabled;
+    }
+    if key_bindings.just_pressed(KeyAction::Mute, &keyboard) {
+        audio_config.muted = !audio_config.muted;
+    }
+    if key_bindings.just_pressed(KeyAction::PausePhysics, &keyboard) {
+        sim_speed.paused = !sim_speed.paused;
+    }
+    if key_bindings.just_pressed(KeyAction::SlowDown, &keyboard) {
+        sim_speed.slower();
+    }
+    if key_bindings.just_pressed(KeyAction::SpeedUp, &keyboard) {
+        sim_speed.faster();
+    }
+    if key_bindings.just_pressed(KeyAction::Step, &keyboard) {
+        sim_speed.step();
+    }
+    if key_bindings.just_pressed(KeyAction::Inspect, &keyboard) {
+        inspector.active = !inspector.active;
+    }
+    if key_bindings.just_pressed(KeyAction::Screenshot, &keyboard) {
+        commands
+            .spawn(Screenshot::primary_window())
+            .observe(save_to_disk(screenshot_path()));
+    }
+}
+
+/// Screenshot file name (native: timestamped in the working directory, web: downloaded)
+fn screenshot_path() -> String {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let secs = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        format!("speaki-box-{}.png", secs)
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    "speaki-box.png".to_string()
+}
+
+/// Speaki actions bound in the key map: spawn at the cursor, clear all
+pub fn hotkey_speaki_system(
+    (keyboard, key_bindings): (Res<ButtonInput<KeyCode>>, Res<KeyBindings>),
+    window: Single<&Window>,
+    camera_q: Query<(&Camera, &GlobalTransform)>,
+    speaki_query: Query<Entity, With<Speaki>>,
+    mut spawn_events: MessageWriter<SpawnSpeakiEvent>,
+    mut despawn_events: MessageWriter<DespawnSpeakiEvent>,
+    mut voices: Voices,
+) {
+    if key_bindings.just_pressed(KeyAction::SpawnAtCursor, &keyboard)
+        && let Ok((camera, camera_transform)) = camera_q.single()
+        && let Some(cursor_pos) = window
+            .cursor_position()
+            .and_then(|p| camera.viewport_to_world_2d(camera_transform, p).ok())
+    {
+        spawn_events.write(SpawnSpeakiEvent {
+            position: cursor_pos,
+            velocity: Vec2::ZERO,
+        });
+
+        if let Some(idx) = voices.groups.pick(VoiceCategory::Create) {
+            voices.events.write(PlayVoiceEvent {
+                entity: None,
+                voice_index: idx,
+                volume: voices.config.create_volume,
+                kind: VoiceKind::Create,
+            });
+        }
+    }
+
+    if key_bindings.just_pressed(KeyAction::ClearAll, &keyboard) && !speaki_query.is_empty() {
+        for entity in speaki_query.iter() {
+            despawn_events.write(DespawnSpeakiEvent { entity });
+        }
+
+        if let Some(idx) = voices.groups.pick(VoiceCategory::Remove) {
+            voices.events.write(PlayVoiceEvent {
+                entity: None,
+                voice_index: idx,
+                volume: voices.config.remove_volume,
+                kind: VoiceKind::Remove,
+            });
+        }
+    }
+}
+
+/// Capture the next key press for the action being rebound (Esc cancels)
+pub fn rebind_capture_system(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut key_bindings: ResMut<KeyBindings>,
+) {
+    let Some(action) = key_bindings.listening else {
+        return;
+    };
+
+    if keyboard.just_pressed(KeyCode::Escape) {
+        key_bindings.listening = None;
+        key_bindings.conflict = None;
+        return;
+    }
+
+    // Window drag is a held key, so a lone modifier is fine; everything else
+    // waits for a non-modifier key and records the modifiers held with it
+    let Some(&key) = keyboard
+        .get_just_pressed()
+        .find(|&&key| action == KeyAction::WindowDrag || !KeyChord::is_modifier(key))
+    else {
+        return;
+    };
+
+    let chord = if action == KeyAction::WindowDrag {
+        KeyChord::new(key)
+    } else {
+        KeyChord::with_modifiers(key, &keyboard)
+    };
+
+    // Keys already in use are refused; keep listening for another one
+    key_bindings.conflict = key_bindings.conflict(action, chord);
+    if key_bindings.conflict.is_some() {
+        return;
+    }
+
+    key_bindings.bindings.insert(action, chord);
+    key_bindings.listening = None;
+    key_bindings.save();
+}
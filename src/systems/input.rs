@@ -1,32 +1,71 @@
+use bevy::ecs::system::SystemParam;
+use bevy::picking::hover::Hovered;
+use bevy::picking::pointer::PointerId;
 use bevy::prelude::*;
-use rand::RngExt;
 
 use crate::components::*;
 use crate::events::*;
 use crate::resources::*;
+use crate::systems::Voices;
+
+/// Throw spin below this keeps the speaki's current rotation
+pub const THROW_SPIN_THRESHOLD: f32 = 0.05;
+
+/// Speaki state a click grabs or (Ctrl+click) selects
+type ClickTarget = (&'static mut Transform, Has<Dragged>, Has<Selected>);
+
+/// What a click can start: a drag, a selection box, inspecting, or a new speaki
+#[derive(SystemParam)]
+pub struct ClickActions<'w, 's> {
+    commands: Commands<'w, 's>,
+    drag_state: ResMut<'w, DragState>,
+    selection_box: ResMut<'w, SelectionBox>,
+    inspector: ResMut<'w, Inspector>,
+    z_order: ResMut<'w, ZOrder>,
+    config: Res<'w, GameConfig>,
+    spawn_events: MessageWriter<'w, SpawnSpeakiEvent>,
+    voices: Voices<'w>,
+}
 
 /// Handle mouse input for clicking/grabbing speakis
 pub fn mouse_input_system(
-    mut commands: Commands,
-    mouse_button: Res<ButtonInput<MouseButton>>,
-    keyboard: Res<ButtonInput<KeyCode>>,
-    window: Single<&Window>,
-    camera_q: Query<(&Camera, &GlobalTransform)>,
-    speaki_query: Query<(Entity, &Transform, &SpeakiSize), With<Speaki>>,
-    mut drag_state: ResMut<DragState>,
-    config: Res<GameConfig>,
+    mut press_events: MessageReader<Pointer<Press>>,
+    (keyboard, key_bindings): (Res<ButtonInput<KeyCode>>, Res<KeyBindings>),
+    (window, camera_q): (Single<&Window>, Query<(&Camera, &GlobalTransform)>),
+    mut speaki_query: Query<ClickTarget, With<Speaki>>,
+    selected_query: Query<Entity, With<Selected>>,
+    actions: ClickActions,
     time: Res<Time>,
-    mut spawn_events: MessageWriter<SpawnSpeakiEvent>,
-    mut voice_events: MessageWriter<PlayVoiceEvent>,
-    voice_groups: Res<VoiceGroups>,
-    audio_config: Res<AudioConfig>,
 ) {
-    if !mouse_button.just_pressed(MouseButton::Left) {
+    let ClickActions {
+        mut commands,
+        mut drag_state,
+        mut selection_box,
+        mut inspector,
+        mut z_order,
+        config,
+        mut spawn_events,
+        mut voices,
+    } = actions;
+
+    // Entity under the mouse, preferring a speaki over the window behind it
+    let Some(target) = press_events
+        .read()
+        .filter(|e| e.pointer_id == PointerId::Mouse && e.event.button == PointerButton::Primary)
+        .map(|e| e.entity)
+        .reduce(|best, entity| {
+            if speaki_query.contains(entity) || !speaki_query.contains(best) {
+                entity
+            } else {
+                best
+            }
+        })
+    else {
         return;
-    }
+    };
 
-    // Skip if Alt is pressed (window drag mode)
-    if keyboard.pressed(KeyCode::AltLeft) {
+    // Skip if the window drag key is held
+    if key_bindings.pressed(KeyAction::WindowDrag, &keyboard) {
         return;
     }
 
@@ -42,46 +81,93 @@ pub fn mouse_input_system(
         return;
     };
 
+    // Inspect mode: clicking picks the speaki to inspect instead of grabbing
+    if inspector.active {
+        if speaki_query.contains(target) {
+            inspector.target = Some(target);
+        }
+        return;
+    }
+
     let current_time = time.elapsed_secs();
+    let shift = keyboard.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    let ctrl = keyboard.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+
+    // Clicked on a speaki (picking already skips transparent pixels)
+    if let Ok((mut transform, held, selected)) = speaki_query.get_mut(target) {
+        // Ctrl+click toggles selection instead of grabbing
+        if ctrl {
+            if selected {
+                commands.entity(target).remove::<Selected>();
+            } else {
+                commands.entity(target).insert(Selected);
+            }
+            return;
+        }
 
-    // Check if clicking on existing speaki (reverse order for topmost first)
-    let mut speakis: Vec<_> = speaki_query.iter().collect();
-    speakis.reverse();
+        // Already held by someone else (LAN multiplayer)
+        if held {
+            return;
+        }
 
-    for (entity, transform, size) in speakis {
-        let dx = cursor_pos.x - transform.translation.x;
-        let dy = cursor_pos.y - transform.translation.y;
-        let dist = (dx * dx + dy * dy).sqrt();
+        // Start dragging
+        drag_state.is_dragging = true;
+        drag_state.dragged_entity = Some(target);
+        drag_state.grab_offset = grab_offset(&transform, cursor_pos);
+        drag_state.history.start(current_time, cursor_pos);
+        drag_state.group.clear();
+
+        // Bring to front
+        transform.translation.z = z_order.next();
+
+        // Add Dragged marker
+        commands.entity(target).insert(Dragged);
+
+        if selected {
+            // Grabbing a selected speaki drags the whole selection along
+            for entity in selected_query.iter().filter(|&e| e != target) {
+                if let Ok((mut transform, false, _)) = speaki_query.get_mut(entity) {
+                    drag_state
+                        .group
+                        .push((entity, transform.translation.truncate() - cursor_pos));
+                    transform.translation.z = z_order.next();
+                    commands.entity(entity).insert(Dragged);
+                }
+            }
+        } else {
+            clear_selection(&mut commands, &selected_query);
+        }
 
-        if dist < size.0 / 2.0 {
-            // Start dragging
-            drag_state.is_dragging = true;
-            drag_state.dragged_entity = Some(entity);
-            drag_state.last_start_pos = cursor_pos;
-            drag_state.last_start_time = current_time;
+        // Play drag voice
+        if let Some(idx) = voices.groups.pick(VoiceCategory::Drag) {
+            voices.events.write(PlayVoiceEvent {
+                entity: Some(target),
+                voice_index: idx,
+                volume: voices.config.grab_volume,
+                kind: VoiceKind::Grab,
+            });
+        }
 
-            // Add Dragged marker
-            commands.entity(entity).insert(Dragged);
+        return;
+    }
 
-            // Play drag voice
-            if !voice_groups.drag.is_empty() {
-                let idx = voice_groups.drag[rand::rng().random_range(0..voice_groups.drag.len())];
-                voice_events.write(PlayVoiceEvent {
-                    entity: Some(entity),
-                    voice_index: idx,
-                    volume: audio_config.grab_volume,
-                });
-            }
+    // Shift+drag on empty space: rubber band selection
+    if shift {
+        selection_box.start = Some(cursor_pos);
+        selection_box.end = cursor_pos;
+        return;
+    }
 
-            return;
-        }
+    if !ctrl {
+        clear_selection(&mut commands, &selected_query);
     }
 
     // Clicked on empty space: create new speaki
     if config.click_to_add {
         drag_state.is_dragging = true;
-        drag_state.last_start_pos = cursor_pos;
-        drag_state.last_start_time = current_time;
+        drag_state.grab_offset = Vec2::ZERO;
+        drag_state.history.start(current_time, cursor_pos);
+        drag_state.group.clear();
         drag_state.last_click_time = current_time;
 
         spawn_events.write(SpawnSpeakiEvent {
@@ -90,22 +176,38 @@ pub fn mouse_input_system(
         });
 
         // Play create voice (entity will be set after spawn)
-        if let Some(&idx) = voice_groups.create.first() {
-            voice_events.write(PlayVoiceEvent {
+        if let Some(idx) = voices.groups.pick(VoiceCategory::Create) {
+            voices.events.write(PlayVoiceEvent {
                 entity: None, // New speaki, will be spawned separately
                 voice_index: idx,
-                volume: audio_config.create_volume,
+                volume: voices.config.create_volume,
+                kind: VoiceKind::Create,
             });
         }
     }
 }
 
+fn clear_selection(commands: &mut Commands, selected_query: &Query<Entity, With<Selected>>) {
+    for entity in selected_query.iter() {
+        commands.entity(entity).remove::<Selected>();
+    }
+}
+
 /// Update dragged speaki position
 pub fn drag_update_system(
     window: Single<&Window>,
     camera_q: Query<(&Camera, &GlobalTransform)>,
-    mut query: Query<(&mut Transform, &mut Velocity), With<Dragged>>,
+    mut query: Query<
+        (
+            &mut Transform,
+            &mut Velocity,
+            &mut RotationState,
+            &SpeakiSize,
+        ),
+        With<Dragged>,
+    >,
     mut drag_state: ResMut<DragState>,
+    physics: Res<PhysicsConfig>,
     time: Res<Time>,
 ) {
     if !drag_state.is_dragging {
@@ -123,20 +225,52 @@ pub fn drag_update_system(
         return;
     };
 
-    let current_time = time.elapsed_secs();
-
-    // Update reference point every 100ms
-    if current_time - drag_state.last_start_time > 0.1 {
-        drag_state.last_start_time = current_time;
-        drag_state.last_start_pos = cursor_pos;
+    // Record cursor history for the throw estimate
+    drag_state.history.push(time.elapsed_secs(), cursor_pos);
+
+    // Move the rest of a dragged selection, keeping their offsets
+    for &(entity, offset) in &drag_state.group {
+        if let Ok((mut transform, mut vel, mut rot, size)) = query.get_mut(entity) {
+            let target = cursor_pos + offset;
+            if physics.spring_drag {
+                spring_drag(
+                    &transform,
+                    &mut vel,
+                    &mut rot,
+                    size.0,
+                    Vec2::ZERO,
+                    target,
+                    &physics,
+                );
+            } else {
+                transform.translation.x = target.x;
+                transform.translation.y = target.y;
+                vel.x = 0.0;
+                vel.y = 0.0;
+            }
+        }
     }
 
     // Move dragged speaki to cursor
-    for (mut transform, mut vel) in query.iter_mut() {
-        transform.translation.x = cursor_pos.x;
-        transform.translation.y = cursor_pos.y;
-        vel.x = 0.0;
-        vel.y = 0.0;
+    if let Some(entity) = drag_state.dragged_entity
+        && let Ok((mut transform, mut vel, mut rot, size)) = query.get_mut(entity)
+    {
+        if physics.spring_drag {
+            spring_drag(
+                &transform,
+                &mut vel,
+                &mut rot,
+                size.0,
+                drag_state.grab_offset,
+                cursor_pos,
+                &physics,
+            );
+        } else {
+            transform.translation.x = cursor_pos.x;
+            transform.translation.y = cursor_pos.y;
+            vel.x = 0.0;
+            vel.y = 0.0;
+        }
     }
 }
 
@@ -144,9 +278,8 @@ pub fn drag_update_system(
 pub fn drag_release_system(
     mut commands: Commands,
     mouse_button: Res<ButtonInput<MouseButton>>,
-    window: Single<&Window>,
-    camera_q: Query<(&Camera, &GlobalTransform)>,
-    mut query: Query<(Entity, &mut Velocity, &mut IdleVoiceTimer), With<Dragged>>,
+    (window, camera_q): (Single<&Window>, Query<(&Camera, &GlobalTransform)>),
+    mut query: Query<(&mut Velocity, &mut RotationState, &mut IdleVoiceTimer), With<Dragged>>,
     mut drag_state: ResMut<DragState>,
     physics: Res<PhysicsConfig>,
     time: Res<Time>,
@@ -171,17 +304,27 @@ pub fn drag_release_system(
     };
 
     let current_time = time.elapsed_secs();
-    let drag_time = current_time - drag_state.last_start_time;
 
-    // Calculate throw velocity
-    let delta = cursor_pos - drag_state.last_start_pos;
-    let time_factor = (drag_time / 0.05).max(0.001);
-    let throw_vel = delta / time_factor * physics.cursor_throwing_power;
+    // Calculate throw velocity and spin from the recent cursor path
+    drag_state.history.push(current_time, cursor_pos);
+    let (throw_vel, spin) = physics.throw(&drag_state.history, current_time);
+
+    // A dragged selection is thrown as one
+    let dragged = drag_state.dragged_entity;
+    let group = drag_state.group.drain(..).map(|(entity, _)| entity);
+    for entity in dragged.into_iter().chain(group) {
+        let Ok((mut vel, mut rot, mut idle_timer)) = query.get_mut(entity) else {
+            continue;
+        };
 
-    for (entity, mut vel, mut idle_timer) in query.iter_mut() {
         vel.x = throw_vel.x;
         vel.y = throw_vel.y;
 
+        // Curved drags spin the speaki, straight ones keep its current spin
+        if spin.abs() > THROW_SPIN_THRESHOLD {
+            rot.speed = spin;
+        }
+
         // Reset idle timer
         idle_timer.last_idle_time = current_time;
 
@@ -220,56 +363,74 @@ pub fn spawn_speaki_system(
     }
 }
 
-/// Handle right-click to delete speaki
-pub fn right_click_delete_system(
-    mouse_button: Res<ButtonInput<MouseButton>>,
-    window: Single<&Window>,
-    camera_q: Query<(&Camera, &GlobalTransform)>,
-    speaki_query: Query<(Entity, &Transform, &SpeakiSize), With<Speaki>>,
+/// Handle right-click on a speaki: open its context menu, or delete it right away
+pub fn right_click_system(
+    mut press_events: MessageReader<Pointer<Press>>,
+    speaki_query: Query<Has<Selected>, With<Speaki>>,
+    selected_query: Query<Entity, With<Selected>>,
+    config: Res<GameConfig>,
+    mut context_menu: ResMut<ContextMenu>,
     mut despawn_events: MessageWriter<DespawnSpeakiEvent>,
-    mut voice_events: MessageWriter<PlayVoiceEvent>,
-    voice_groups: Res<VoiceGroups>,
-    audio_config: Res<AudioConfig>,
+    mut voices: Voices,
 ) {
-    if !mouse_button.just_pressed(MouseButton::Right) {
-        return;
-    }
+    for event in press_events.read() {
+        if event.pointer_id != PointerId::Mouse || event.event.button != PointerButton::Secondary {
+            continue;
+        }
 
-    let Ok((camera, camera_transform)) = camera_q.single() else {
-        return;
-    };
+        // Only speakis can be deleted (not the window behind them)
+        let entity = event.entity;
+        let Ok(selected) = speaki_query.get(entity) else {
+            continue;
+        };
 
-    let Some(cursor_pos) = window
-        .cursor_position()
-        .and_then(|p| camera.viewport_to_world_2d(camera_transform, p).ok())
-    else {
-        return;
-    };
+        // Open the context menu unless instant delete is on
+        if !config.instant_delete {
+            context_menu.target = Some(entity);
+            context_menu.position = event.pointer_location.position;
+            continue;
+        }
 
-    // Check if clicking on existing speaki (reverse order for topmost first)
-    let mut speakis: Vec<_> = speaki_query.iter().collect();
-    speakis.reverse();
+        // Delete speaki, or the whole selection it belongs to
+        if selected {
+            for entity in selected_query.iter() {
+                despawn_events.write(DespawnSpeakiEvent { entity });
+            }
+        } else {
+            despawn_events.write(DespawnSpeakiEvent { entity });
+        }
 
-    for (entity, transform, size) in speakis {
-        let dx = cursor_pos.x - transform.translation.x;
-        let dy = cursor_pos.y - transform.translation.y;
-        let dist = (dx * dx + dy * dy).sqrt();
+        // Play remove voice (random)
+        if let Some(idx) = voices.groups.pick(VoiceCategory::Remove) {
+            voices.events.write(PlayVoiceEvent {
+                entity: Some(entity),
+                voice_index: idx,
+                volume: voices.config.remove_volume,
+                kind: VoiceKind::Remove,
+            });
+        }
+    }
+}
 
-        if dist < size.0 / 2.0 {
-            // Delete speaki
-            despawn_events.write(DespawnSpeakiEvent { entity });
+/// Put newly spawned speakis on top and keep z values within the camera range
+pub fn z_order_system(
+    mut z_order: ResMut<ZOrder>,
+    mut query: Query<(&mut Transform, Ref<Speaki>)>,
+) {
+    for (mut transform, speaki) in query.iter_mut() {
+        if speaki.is_added() {
+            transform.translation.z = z_order.next();
+        }
+    }
 
-            // Play remove voice (random)
-            if !voice_groups.remove.is_empty() {
-                let idx =
-                    voice_groups.remove[rand::rng().random_range(0..voice_groups.remove.len())];
-                voice_events.write(PlayVoiceEvent {
-                    entity: Some(entity),
-                    voice_index: idx,
-                    volume: audio_config.remove_volume,
-                });
-            }
-            return;
+    if z_order.top > ZOrder::LIMIT {
+        // Compact z values while keeping the stacking order
+        let mut stack: Vec<_> = query.iter_mut().collect();
+        stack.sort_by(|a, b| a.0.translation.z.total_cmp(&b.0.translation.z));
+
+        z_order.top = 0.0;
+        for (mut transform, _) in stack {
+            transform.translation.z = z_order.next();
         }
     }
 }
@@ -284,6 +445,39 @@ pub fn despawn_speaki_system(
     }
 }
 
+/// Grab point in the speaki's local space, so it stays put as the speaki rotates
+pub fn grab_offset(transform: &Transform, point: Vec2) -> Vec2 {
+    let world_offset = point.extend(0.0) - transform.translation.with_z(0.0);
+    (transform.rotation.inverse() * world_offset).truncate()
+}
+
+/// Pull a held speaki by its grab point toward the target (spring drag)
+pub fn spring_drag(
+    transform: &Transform,
+    vel: &mut Velocity,
+    rot: &mut RotationState,
+    size: f32,
+    grab_offset: Vec2,
+    target: Vec2,
+    physics: &PhysicsConfig,
+) {
+    let arm = (transform.rotation * grab_offset.extend(0.0)).truncate();
+    let grab_point = transform.translation.truncate() + arm;
+    let force = (target - grab_point) * physics.drag_stiffness;
+
+    // Gravity keeps acting so the speaki hangs and swings from the grab point
+    let gravity = physics.gravity_direction * physics.gravity;
+    vel.x = (vel.x + force.x + gravity.x) * physics.drag_damping;
+    vel.y = (vel.y + force.y + gravity.y) * physics.drag_damping;
+
+    // Off-center pull turns the speaki (solid disc: I = m r^2 / 2)
+    if physics.rotation_speed > 0.0 {
+        let radius = size / 2.0;
+        let angular = arm.perp_dot(force) * 2.0 / (radius * radius);
+        rot.speed = (rot.speed + angular / physics.rotation_speed) * physics.drag_damping;
+    }
+}
+
 /// Helper function to spawn a speaki
 pub fn spawn_speaki(
     commands: &mut Commands,
@@ -295,9 +489,35 @@ pub fn spawn_speaki(
 ) -> Entity {
     // Check if this speaki should be shiny
     let is_shiny = shiny_config.enabled && rand::random::<f32>() < shiny_config.spawn_chance;
+    let shiny_color = is_shiny.then(|| {
+        let [r, g, b] = shiny_config.glow_color;
+        Color::srgb(r, g, b)
+    });
+
+    spawn_speaki_with(
+        commands,
+        position,
+        velocity,
+        size,
+        shiny_color,
+        sprites,
+        shiny_config,
+    )
+}
 
+/// Spawn a speaki with a given shiny color (`None` for a normal speaki)
+pub fn spawn_speaki_with(
+    commands: &mut Commands,
+    position: Vec2,
+    velocity: Vec2,
+    size: f32,
+    shiny_color: Option<Color>,
+    sprites: &SpriteAssets,
+    shiny_config: &ShinyConfig,
+) -> Entity {
     let mut entity_commands = commands.spawn((
         Speaki,
+        SpeakiName::random(),
         Velocity::new(velocity.x, velocity.y),
         RotationState::default(),
         SpriteState::default(),
@@ -307,18 +527,20 @@ pub fn spawn_speaki(
         SpeakiSize(size),
         Transform::from_translation(position.extend(0.0)),
         Visibility::default(),
+        Pickable::default(),
+        Hovered::default(),
     ));
 
     // Add sprite if assets are loaded
     if sprites.loaded && !sprites.states.is_empty() {
-        let sprite_color = if is_shiny {
+        let sprite_color = if let Some(base_color) = shiny_color {
             // HDR color for bloom effect (values > 1.0 trigger bloom)
-            let [r, g, b] = shiny_config.glow_color;
+            let base = base_color.to_srgba();
             let intensity = shiny_config.glow_intensity;
             Color::srgb(
-                r * intensity,
-                g * intensity,
-                b * intensity,
+                base.red * intensity,
+                base.green * intensity,
+                base.blue * intensity,
             )
         } else {
             Color::WHITE
@@ -331,10 +553,9 @@ pub fn spawn_speaki(
             ..default()
         });
 
-        if is_shiny {
-            let [r, g, b] = shiny_config.glow_color;
+        if let Some(base_color) = shiny_color {
             entity_commands.insert(Shiny {
-                base_color: Color::srgb(r, g, b),
+                base_color,
                 ..default()
             });
         }
@@ -343,13 +564,16 @@ pub fn spawn_speaki(
     entity_commands.id()
 }
 
-/// Allow dragging window with Alt + Left Click
+/// Allow dragging window with the window drag key (Alt by default) + Left Click
 pub fn window_drag_system(
     mouse: Res<ButtonInput<MouseButton>>,
     keyboard: Res<ButtonInput<KeyCode>>,
+    key_bindings: Res<KeyBindings>,
     mut windows: Query<&mut Window>,
 ) {
-    if keyboard.pressed(KeyCode::AltLeft) && mouse.just_pressed(MouseButton::Left) {
+    if key_bindings.pressed(KeyAction::WindowDrag, &keyboard)
+        && mouse.just_pressed(MouseButton::Left)
+    {
         for mut window in windows.iter_mut() {
             window.start_drag_move();
         }
@@ -0,0 +1,458 @@
+use std::sync::Mutex;
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::time::Duration;
+
+use bevy::prelude::*;
+use rand::RngExt;
+
+use crate::components::*;
+use crate::events::*;
+use crate::resources::*;
+use crate::systems::Voices;
+
+/// Length (seconds) of each level measurement fed from a wav file
+const WAV_CHUNK: f32 = 0.02;
+/// Seconds without input before the level drops back to silence
+const MIC_TIMEOUT: f32 = 0.25;
+/// Input level (RMS, 0-1) of a clap or shout that startles the speakis
+const STARTLE_LEVEL: f32 = 0.3;
+/// Seconds before the speakis can be startled again
+const STARTLE_COOLDOWN: f32 = 1.0;
+/// Upward speed (px/frame) of a startled jump
+const STARTLE_JUMP: f32 = 18.0;
+/// Input level (RMS, 0-1) that counts as someone talking
+const SPEECH_LEVEL: f32 = 0.03;
+/// Seconds of talking before the speakis turn toward the voice
+const SPEECH_MIN: f32 = 0.6;
+/// Seconds of quiet that end a sentence (and start the replies)
+const SPEECH_GAP: f32 = 0.5;
+/// Speakis that reply once someone stops talking
+const MIC_REPLIES: usize = 2;
+
+/// Loudness of one chunk of microphone input
+#[derive(Clone, Copy, Default)]
+pub struct MicLevel {
+    pub level: f32,   // RMS of the louder channel (0-1)
+    pub balance: f32, // -1 = all left, 1 = all right (0 for mono input)
+}
+
+/// Receiving end of the microphone (levels measured on the capture thread)
+#[derive(Resource)]
+pub struct MicInput {
+    levels: Mutex<Receiver<MicLevel>>,
+}
+
+/// What the microphone has been hearing
+#[derive(Resource, Default)]
+pub struct MicState {
+    pub level: MicLevel,
+    pub speech_time: f32,  // Seconds of the current sentence
+    pub silence_time: f32, // Seconds since the input was last above speech level
+    pub last_startle: f32,
+    pub last_input: f32, // When the last level arrived
+}
+
+impl MicState {
+    /// Whether the current level is a clap or shout (at most once per cooldown)
+    fn startled(&mut self, current_time: f32) -> bool {
+        if self.level.level >= STARTLE_LEVEL && current_time - self.last_startle > STARTLE_COOLDOWN
+        {
+            self.last_startle = current_time;
+            return true;
+        }
+        false
+    }
+
+    /// Count the current level toward talking or silence
+    fn listen(&mut self, delta: f32) {
+        if self.level.level >= SPEECH_LEVEL {
+            self.speech_time += delta;
+            self.silence_time = 0.0;
+        } else {
+            self.silence_time += delta;
+        }
+    }
+
+    /// Someone has been talking long enough to turn toward
+    fn talking(&self) -> bool {
+        self.speech_time >= SPEECH_MIN && self.silence_time == 0.0
+    }
+
+    /// Whether a sentence worth answering just ended (resets it either way)
+    fn sentence_over(&mut self) -> bool {
+        if self.silence_time < SPEECH_GAP {
+            return false;
+        }
+        let answer = self.speech_time >= SPEECH_MIN;
+        self.speech_time = 0.0;
+        answer
+    }
+}
+
+/// Start listening on a background thread: the wav file in SPEAKI_MIC_WAV
+/// (looped, as a stand-in for a real microphone) or the default input device
+pub fn start_mic_system(mut commands: Commands) {
+    let (tx, rx) = mpsc::channel();
+
+    std::thread::spawn(move || {
+        let result = match std::env::var("SPEAKI_MIC_WAV") {
+            Ok(path) => listen_wav(&path, tx),
+            Err(_) => listen_device(tx),
+        };
+        if let Err(e) = result {
+            warn!("Microphone input stopped: {}", e);
+        }
+    });
+
+    commands.insert_resource(MicInput {
+        levels: Mutex::new(rx),
+    });
+    commands.init_resource::<MicState>();
+}
+
+/// RMS level and left/right balance of interleaved samples
+fn measure(samples: &[f32], channels: usize) -> MicLevel {
+    let mut left = 0.0;
+    let mut right = 0.0;
+    let mut frames = 0;
+    for frame in samples.chunks(channels.max(1)) {
+        let l = frame[0];
+        let r = frame.get(1).copied().unwrap_or(l);
+        left += l * l;
+        right += r * r;
+        frames += 1;
+    }
+    if frames == 0 {
+        return MicLevel::default();
+    }
+
+    let left = (left / frames as f32).sqrt();
+    let right = (right / frames as f32).sqrt();
+    let balance = if left + right > 0.0 {
+        (right - left) / (right + left)
+    } else {
+        0.0
+    };
+    MicLevel {
+        level: left.max(right),
+        balance,
+    }
+}
+
+/// Feed levels from a wav file in real time, looping forever
+fn listen_wav(path: &str, tx: Sender<MicLevel>) -> Result<(), Box<dyn std::error::Error>> {
+    let levels = wav_levels(path)?;
+    info!("Microphone stand-in: {}", path);
+
+    loop {
+        for &level in &levels {
+            if tx.send(level).is_err() {
+                return Ok(());
+            }
+            std::thread::sleep(Duration::from_secs_f32(WAV_CHUNK));
+        }
+    }
+}
+
+/// Levels of each WAV_CHUNK of a wav file
+fn wav_levels(path: &str) -> Result<Vec<MicLevel>, Box<dyn std::error::Error>> {
+    let mut reader = hound::WavReader::open(path)?;
+    let spec = reader.spec();
+    let samples: Vec<f32> = match spec.sample_format {
+        hound::SampleFormat::Float => reader.samples::<f32>().collect::<Result<_, _>>()?,
+        hound::SampleFormat::Int => {
+            let scale = (1_i64 << (spec.bits_per_sample - 1)) as f32;
+            reader
+                .samples::<i32>()
+                .map(|s| s.map(|s| s as f32 / scale))
+                .collect::<Result<_, _>>()?
+        }
+    };
+    if samples.is_empty() {
+        return Err(format!("{path} has no samples").into());
+    }
+
+    let channels = spec.channels as usize;
+    let frames = ((spec.sample_rate as f32 * WAV_CHUNK) as usize).max(1);
+    Ok(samples
+        .chunks(frames * channels)
+        .map(|chunk| measure(chunk, channels))
+        .collect())
+}
+
+/// Feed levels from the default input device
+fn listen_device(tx: Sender<MicLevel>) -> Result<(), Box<dyn std::error::Error>> {
+    use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+
+    let device = cpal::default_host()
+        .default_input_device()
+        .ok_or("no input device")?;
+    let config = device.default_input_config()?;
+    info!("Microphone: {}", device.name().unwrap_or_default());
+
+    let stream = match config.sample_format() {
+        cpal::SampleFormat::F32 => build_stream::<f32>(&device, &config.into(), tx)?,
+        cpal::SampleFormat::I16 => build_stream::<i16>(&device, &config.into(), tx)?,
+        cpal::SampleFormat::U16 => build_stream::<u16>(&device, &config.into(), tx)?,
+        format => return Err(format!("unsupported sample format {format}").into()),
+    };
+    stream.play()?;
+
+    // The stream stops when dropped, so keep this thread around
+    loop {
+        std::thread::park();
+    }
+}
+
+fn build_stream<T>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    tx: Sender<MicLevel>,
+) -> Result<cpal::Stream, cpal::BuildStreamError>
+where
+    T: cpal::SizedSample,
+    f32: cpal::FromSample<T>,
+{
+    use cpal::traits::DeviceTrait;
+
+    let channels = config.channels as usize;
+    let mut buffer = Vec::new();
+    device.build_input_stream(
+        config,
+        move |data: &[T], _| {
+            buffer.clear();
+            buffer.extend(data.iter().map(|s| s.to_sample::<f32>()));
+            let _ = tx.send(measure(&buffer, channels));
+        },
+        |e| warn!("Microphone error: {}", e),
+        None,
+    )
+}
+
+/// Speaki state a microphone reaction changes
+type Listening = (
+    Entity,
+    &'static Transform,
+    &'static mut Velocity,
+    &'static mut Sprite,
+    &'static mut SpriteState,
+    &'static mut IdleVoiceTimer,
+);
+
+/// React to the microphone: loud sounds startle the speakis, talking makes
+/// them face the voice and a couple of them reply when it stops
+pub fn mic_reaction_system(
+    mic: Res<MicInput>,
+    mut state: ResMut<MicState>,
+    mut query: Query<Listening, (With<Speaki>, Without<Dragged>)>,
+    mut voices: Voices,
+    (physics, image_groups): (Res<PhysicsConfig>, Res<ImageGroups>),
+    window: Single<&Window>,
+    time: Res<Time>,
+) {
+    let current_time = time.elapsed_secs();
+
+    // Loudest chunk since last frame
+    let mut loudest: Option<MicLevel> = None;
+    let mut stopped = false;
+    if let Ok(rx) = mic.levels.lock() {
+        loop {
+            match rx.try_recv() {
+                Ok(level) => {
+                    if loudest.is_none_or(|l| level.level > l.level) {
+                        loudest = Some(level);
+                    }
+                }
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    stopped = true;
+                    break;
+                }
+            }
+        }
+    }
+    // Keep the last level between chunks, but go quiet once the input stalls
+    // or the capture thread is gone
+    match loudest {
+        Some(level) => {
+            state.level = level;
+            state.last_input = current_time;
+        }
+        None if stopped || current_time - state.last_input > MIC_TIMEOUT => {
+            state.level = MicLevel::default();
+        }
+        None => {}
+    }
+    let level = state.level;
+
+    // Clap or shout: everyone jumps and looks upset
+    if state.startled(current_time) {
+        for (_, _, mut vel, _, mut sprite_state, _) in query.iter_mut() {
+            let jump =
+                -physics.gravity_direction * STARTLE_JUMP * (0.7 + rand::random::<f32>() * 0.6);
+            vel.x += jump.x;
+            vel.y += jump.y;
+            if !image_groups.sad.is_empty() {
+                sprite_state.current_index =
+                    image_groups.sad[rand::rng().random_range(0..image_groups.sad.len())];
+            }
+        }
+        return;
+    }
+
+    state.listen(time.delta_secs());
+
+    // Talking: face the voice (stereo input places it left or right, mono
+    // input in the middle of the box)
+    if state.talking() {
+        let voice_x = level.balance * window.width() / 2.0;
+        for (_, transform, _, mut sprite, ..) in query.iter_mut() {
+            // Sprites face right
+            sprite.flip_x = voice_x < transform.translation.x;
+        }
+    }
+
+    // Sentence over: a couple of speakis answer
+    if state.sentence_over() {
+        let mut speakis: Vec<_> = query.iter_mut().collect();
+        for _ in 0..MIC_REPLIES.min(speakis.len()) {
+            let i = rand::rng().random_range(0..speakis.len());
+            let (entity, .., mut idle_timer) = speakis.swap_remove(i);
+            if let Some(idx) = voices.groups.pick(VoiceCategory::Idle) {
+                voices.events.write(PlayVoiceEvent {
+                    entity: Some(entity),
+                    voice_index: idx,
+                    volume: voices.config.idle_volume,
+                    kind: VoiceKind::Idle,
+                });
+            }
+            idle_timer.last_idle_time = current_time;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Write a 16-bit wav of `seconds` of a sine wave per (left, right) amplitude
+    fn write_wav(name: &str, channels: u16, sections: &[(f32, f32, f32)]) -> String {
+        let path =
+            std::env::temp_dir().join(format!("speaki-mic-{}-{name}.wav", std::process::id()));
+        let spec = hound::WavSpec {
+            channels,
+            sample_rate: 8000,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+        for &(seconds, left, right) in sections {
+            for i in 0..(seconds * 8000.0) as usize {
+                let wave = (i as f32 * 440.0 / 8000.0 * std::f32::consts::TAU).sin();
+                writer
+                    .write_sample((wave * left * i16::MAX as f32) as i16)
+                    .unwrap();
+                if channels == 2 {
+                    writer
+                        .write_sample((wave * right * i16::MAX as f32) as i16)
+                        .unwrap();
+                }
+            }
+        }
+        writer.finalize().unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    /// Run the state through the levels of a wav file, one chunk per frame
+    fn hear(state: &mut MicState, levels: &[MicLevel]) -> (usize, bool, usize) {
+        let (mut startles, mut talked, mut answers) = (0, false, 0);
+        for (i, &level) in levels.iter().enumerate() {
+            state.level = level;
+            if state.startled(STARTLE_COOLDOWN + i as f32 * WAV_CHUNK) {
+                startles += 1;
+                continue;
+            }
+            state.listen(WAV_CHUNK);
+            talked |= state.talking();
+            if state.sentence_over() {
+                answers += 1;
+            }
+        }
+        (startles, talked, answers)
+    }
+
+    #[test]
+    fn measure_silence() {
+        let level = measure(&[0.0; 64], 2);
+        assert_eq!(level.level, 0.0);
+        assert_eq!(level.balance, 0.0);
+        assert_eq!(measure(&[], 1).level, 0.0);
+    }
+
+    #[test]
+    fn measure_mono() {
+        let level = measure(&[0.5, -0.5, 0.5, -0.5], 1);
+        assert!((level.level - 0.5).abs() < 1e-6);
+        assert_eq!(level.balance, 0.0);
+    }
+
+    #[test]
+    fn measure_balance() {
+        let left = measure(&[0.5, 0.0, -0.5, 0.0], 2);
+        assert!((left.level - 0.5).abs() < 1e-6);
+        assert_eq!(left.balance, -1.0);
+
+        let right = measure(&[0.0, 0.5, 0.0, -0.5], 2);
+        assert_eq!(right.balance, 1.0);
+
+        let mostly_right = measure(&[0.1, 0.3, -0.1, -0.3], 2);
+        assert!((mostly_right.balance - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn wav_balance() {
+        let path = write_wav("balance", 2, &[(0.1, 0.05, 0.2)]);
+        let levels = wav_levels(&path).unwrap();
+        assert_eq!(levels.len(), 5);
+        assert!(levels.iter().all(|l| l.balance > 0.5));
+    }
+
+    #[test]
+    fn empty_wav_is_an_error() {
+        let path = write_wav("empty", 1, &[]);
+        assert!(wav_levels(&path).is_err());
+    }
+
+    #[test]
+    fn clap_startles_once() {
+        // A quiet murmur, then a short clap (sine RMS is amplitude / sqrt 2)
+        let path = write_wav(
+            "clap",
+            1,
+            &[(0.2, 0.01, 0.0), (0.1, 0.9, 0.0), (0.3, 0.0, 0.0)],
+        );
+        let (startles, talked, answers) =
+            hear(&mut MicState::default(), &wav_levels(&path).unwrap());
+        assert_eq!(startles, 1);
+        assert!(!talked);
+        assert_eq!(answers, 0);
+    }
+
+    #[test]
+    fn sentence_gets_answered() {
+        let path = write_wav("speech", 1, &[(1.0, 0.1, 0.0), (0.6, 0.0, 0.0)]);
+        let (startles, talked, answers) =
+            hear(&mut MicState::default(), &wav_levels(&path).unwrap());
+        assert_eq!(startles, 0);
+        assert!(talked);
+        assert_eq!(answers, 1);
+    }
+
+    #[test]
+    fn short_remark_is_ignored() {
+        let path = write_wav("remark", 1, &[(0.3, 0.1, 0.0), (0.6, 0.0, 0.0)]);
+        let (_, talked, answers) = hear(&mut MicState::default(), &wav_levels(&path).unwrap());
+        assert!(!talked);
+        assert_eq!(answers, 0);
+    }
+}
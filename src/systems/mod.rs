@@ -1,11 +1,41 @@
 pub mod animation;
 pub mod audio;
+pub mod captions;
+#[cfg(all(feature = "control", not(target_arch = "wasm32")))]
+pub mod control;
+pub mod gamepad;
+pub mod hotkeys;
 pub mod input;
+#[cfg(all(feature = "mic", not(target_arch = "wasm32")))]
+pub mod mic;
+pub mod music;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod net;
 pub mod physics;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod portal;
+pub mod selection;
+pub mod touch;
 pub mod ui;
+pub mod undo;
 
 pub use animation::*;
 pub use audio::*;
+pub use captions::*;
+#[cfg(all(feature = "control", not(target_arch = "wasm32")))]
+pub use control::*;
+pub use gamepad::*;
+pub use hotkeys::*;
 pub use input::*;
+#[cfg(all(feature = "mic", not(target_arch = "wasm32")))]
+pub use mic::*;
+pub use music::*;
+#[cfg(not(target_arch = "wasm32"))]
+pub use net::*;
 pub use physics::*;
+#[cfg(not(target_arch = "wasm32"))]
+pub use portal::*;
+pub use selection::*;
+pub use touch::*;
 pub use ui::*;
+pub use undo::*;
@@ -0,0 +1,169 @@
+use bevy::prelude::*;
+use bevy_kira_audio::prelude::*;
+use std::time::Duration;
+
+use crate::resources::*;
+
+/// Tracks bundled under assets/music as (name, path), played before the ones
+/// from the user's music folder
+const BUILTIN_TRACKS: &[(&str, &str)] = &[("Music Box", "music/music-box.ogg")];
+/// File types picked up from the music folder
+#[cfg(not(target_arch = "wasm32"))]
+const MUSIC_EXTENSIONS: [&str; 2] = ["mp3", "ogg"];
+/// Fade used when the music is paused, resumed or switched off
+const MUSIC_FADE: Duration = Duration::from_millis(500);
+/// Longest share of a track the crossfade may take, so short tracks still play
+const MAX_CROSSFADE_SHARE: f64 = 0.25;
+
+/// Build the playlist from the bundled tracks and the user's music folder
+pub fn setup_music_system(asset_server: Res<AssetServer>, mut player: ResMut<MusicPlayer>) {
+    for (name, path) in BUILTIN_TRACKS {
+        player.tracks.push(MusicTrack {
+            name: name.to_string(),
+            source: asset_server.load(*path),
+        });
+    }
+
+    // The music folder is registered as the "music" asset source in main
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some(entries) = music_dir().and_then(|dir| std::fs::read_dir(dir).ok()) {
+        let mut files: Vec<std::path::PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| {
+                path.extension()
+                    .and_then(|ext| ext.to_str())
+                    .is_some_and(|ext| MUSIC_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+            })
+            .collect();
+        files.sort();
+
+        for path in files {
+            let (Some(stem), Some(file_name)) = (path.file_stem(), path.file_name()) else {
+                continue;
+            };
+            player.tracks.push(MusicTrack {
+                name: stem.to_string_lossy().into_owned(),
+                source: asset_server.load(format!("music://{}", file_name.to_string_lossy())),
+            });
+        }
+    }
+}
+
+/// Play the playlist on the music channel, crossfading between tracks and
+/// pausing while the window is in the background
+pub fn music_player_system(
+    audio_config: Res<AudioConfig>,
+    mut player: ResMut<MusicPlayer>,
+    music_channel: Res<AudioChannel<MusicChannel>>,
+    mut audio_instances: ResMut<Assets<AudioInstance>>,
+    audio_sources: Res<Assets<AudioSource>>,
+    window: Single<&Window>,
+) {
+    // Switched off (or nothing to play): fade out whatever is playing
+    if !audio_config.music_enabled || player.tracks.is_empty() {
+        if let Some(handle) = player.instance.take()
+            && let Some(instance) = audio_instances.get_mut(&handle)
+        {
+            instance.stop(AudioTween::linear(MUSIC_FADE));
+        }
+        player.focus_paused = false;
+        player.requested = None;
+        return;
+    }
+
+    // Pause in the background, resume on focus
+    let background = audio_config.music_focus_pause && !window.focused;
+    if background != player.focus_paused {
+        player.focus_paused = background;
+        if let Some(instance) = player
+            .instance
+            .as_ref()
+            .and_then(|handle| audio_instances.get_mut(handle))
+        {
+            if background {
+                instance.pause(AudioTween::linear(MUSIC_FADE));
+            } else {
+                instance.resume(AudioTween::linear(MUSIC_FADE));
+            }
+        }
+    }
+    if player.focus_paused {
+        return;
+    }
+
+    let current_duration = track_duration(&player, &audio_sources, player.current);
+    let crossfade_secs = clamp_crossfade(audio_config.music_crossfade, current_duration);
+
+    // Next track: one picked in the settings, the following one when the
+    // current track is about to end, or the first one if nothing is playing
+    let state = player
+        .instance
+        .as_ref()
+        .map_or(PlaybackState::Stopped, |handle| music_channel.state(handle));
+    let next = match (player.requested.take(), state) {
+        (Some(requested), _) => Some(requested),
+        (None, PlaybackState::Stopped) => Some(player.next_index()),
+        (None, PlaybackState::Playing { position }) => current_duration
+            .filter(|&duration| position >= duration - crossfade_secs)
+            .map(|_| player.next_index()),
+        _ => None,
+    };
+
+    let Some(index) = next.filter(|&i| i < player.tracks.len()) else {
+        return;
+    };
+
+    // Short enough for both the outgoing and the incoming track
+    let next_duration = track_duration(&player, &audio_sources, Some(index));
+    let crossfade_secs =
+        crossfade_secs.min(clamp_crossfade(audio_config.music_crossfade, next_duration));
+    let crossfade = || AudioTween::linear(Duration::from_secs_f64(crossfade_secs));
+
+    if let Some(instance) = player
+        .instance
+        .as_ref()
+        .and_then(|handle| audio_instances.get_mut(handle))
+    {
+        instance.stop(crossfade());
+    }
+
+    let source = player.tracks[index].source.clone();
+    player.instance = Some(music_channel.play(source).fade_in(crossfade()).handle());
+    player.current = Some(index);
+}
+
+/// Length of a playlist track, once it has loaded
+fn track_duration(
+    player: &MusicPlayer,
+    audio_sources: &Assets<AudioSource>,
+    index: Option<usize>,
+) -> Option<f64> {
+    index
+        .and_then(|i| player.tracks.get(i))
+        .and_then(|track| audio_sources.get(&track.source))
+        .map(|source| source.sound.duration().as_secs_f64())
+}
+
+/// Crossfade setting limited to a share of the track length
+fn clamp_crossfade(setting: f32, duration: Option<f64>) -> f64 {
+    let crossfade = setting.max(0.0) as f64;
+    duration.map_or(crossfade, |duration| {
+        crossfade.min(duration * MAX_CROSSFADE_SHARE)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crossfade_fits_short_tracks() {
+        // Long track: the setting as is
+        assert_eq!(clamp_crossfade(3.0, Some(120.0)), 3.0);
+        // A 2 second jingle would otherwise be skipped right away
+        assert_eq!(clamp_crossfade(3.0, Some(2.0)), 0.5);
+        // Not loaded yet
+        assert_eq!(clamp_crossfade(3.0, None), 3.0);
+        assert_eq!(clamp_crossfade(-1.0, Some(10.0)), 0.0);
+    }
+}
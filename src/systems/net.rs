@@ -0,0 +1,1093 @@
+use std::collections::{HashMap, HashSet};
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use bevy::ecs::system::SystemParam;
+use bevy::picking::pointer::PointerId;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::components::*;
+use crate::events::*;
+use crate::resources::*;
+use crate::systems::{THROW_SPIN_THRESHOLD, Voices, spawn_speaki_with};
+
+/// Default UDP port of the host
+pub const DEFAULT_NET_PORT: u16 = 47900;
+
+/// Snapshot / cursor send rate (30 Hz)
+const SEND_INTERVAL: f32 = 1.0 / 30.0;
+/// Clients repeat hello until the host answers
+const HELLO_INTERVAL: f32 = 1.0;
+/// Clients that have not sent anything for this long are dropped
+const CLIENT_TIMEOUT: f32 = 3.0;
+/// The host itself is always user 0
+const HOST_USER: u32 = 0;
+/// Cursor markers are drawn above speakis
+const CURSOR_Z: f32 = 500.0;
+/// Speakis per snapshot packet (about 180 bytes each), keeping packets under
+/// a typical 1400 byte MTU so they are not fragmented
+const SPEAKIS_PER_PACKET: usize = 6;
+/// Receive buffer size, the largest possible UDP datagram
+const MAX_DATAGRAM: usize = 65536;
+
+/// Command line options for LAN multiplayer
+#[derive(Resource, Clone)]
+pub struct NetArgs {
+    pub mode: NetMode,
+    pub address: SocketAddr,
+}
+
+impl NetArgs {
+    /// Parse `--host [port]` or `--join <host[:port]>`
+    pub fn from_args(args: &[String]) -> Self {
+        let mut result = Self {
+            mode: NetMode::Offline,
+            address: SocketAddr::from(([0, 0, 0, 0], DEFAULT_NET_PORT)),
+        };
+
+        let mut iter = args.iter().peekable();
+        while let Some(arg) = iter.next() {
+            match arg.as_str() {
+                "--host" => {
+                    result.mode = NetMode::Host;
+                    if let Some(port) = iter.peek().and_then(|p| p.parse::<u16>().ok()) {
+                        result.address.set_port(port);
+                        iter.next();
+                    }
+                }
+                "--join" => {
+                    let Some(host) = iter.next() else {
+                        continue;
+                    };
+                    match resolve_host(host) {
+                        Some(address) => {
+                            result.mode = NetMode::Client;
+                            result.address = address;
+                        }
+                        None => warn!("Invalid host address: {}", host),
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        result
+    }
+}
+
+/// Resolve `host:port`, using the default port if none is given
+fn resolve_host(host: &str) -> Option<SocketAddr> {
+    host.to_socket_addrs()
+        .or_else(|_| format!("{}:{}", host, DEFAULT_NET_PORT).to_socket_addrs())
+        .ok()?
+        .next()
+}
+
+/// Message from a client to the host
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ClientMessage {
+    Hello,
+    Cursor { x: f32, y: f32 },
+    Grab { id: u32 },
+    Release { vx: f32, vy: f32, curvature: f32 }, // Throw estimate before throwing power is applied
+    Spawn { x: f32, y: f32 },
+    Remove { id: u32 },
+}
+
+impl ClientMessage {
+    /// Make a client message safe to apply: anyone on the LAN can send to the
+    /// host, so reject non-finite numbers and keep positions inside the box.
+    /// A release with a broken throw still lets go, just without throwing
+    fn sanitized(self, bounds: Rect) -> Option<Self> {
+        let finite = |values: &[f32]| values.iter().all(|v| v.is_finite());
+        let clamp = |x: f32, y: f32| Vec2::new(x, y).clamp(bounds.min, bounds.max);
+
+        match self {
+            ClientMessage::Cursor { x, y } if finite(&[x, y]) => {
+                let position = clamp(x, y);
+                Some(ClientMessage::Cursor {
+                    x: position.x,
+                    y: position.y,
+                })
+            }
+            ClientMessage::Spawn { x, y } if finite(&[x, y]) => {
+                let position = clamp(x, y);
+                Some(ClientMessage::Spawn {
+                    x: position.x,
+                    y: position.y,
+                })
+            }
+            ClientMessage::Release { vx, vy, curvature } if !finite(&[vx, vy, curvature]) => {
+                Some(ClientMessage::Release {
+                    vx: 0.0,
+                    vy: 0.0,
+                    curvature: 0.0,
+                })
+            }
+            ClientMessage::Cursor { .. } | ClientMessage::Spawn { .. } => None,
+            ClientMessage::Hello
+            | ClientMessage::Grab { .. }
+            | ClientMessage::Release { .. }
+            | ClientMessage::Remove { .. } => Some(self),
+        }
+    }
+}
+
+/// Message from the host to a client
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum HostMessage {
+    Welcome {
+        user: u32,
+    },
+    /// One packet of a snapshot; cursors only come with part 0
+    Snapshot {
+        tick: u32,
+        part: u16,
+        parts: u16,
+        speakis: Vec<SpeakiSnapshot>,
+        cursors: Vec<CursorSnapshot>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct SpeakiSnapshot {
+    id: u32,
+    position: [f32; 3], // z is the stacking order
+    angle: f32,
+    size: f32,
+    sprite_index: usize,
+    shiny_color: Option<[f32; 3]>,
+    held_by: Option<u32>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CursorSnapshot {
+    user: u32,
+    position: [f32; 2],
+}
+
+struct RemoteUser {
+    user: u32,
+    cursor: Vec2,
+    last_seen: f32,
+    held: Option<Entity>,
+}
+
+/// Host side of a LAN session
+#[derive(Resource)]
+pub struct NetHost {
+    socket: UdpSocket,
+    clients: HashMap<SocketAddr, RemoteUser>,
+    next_user: u32,
+    next_id: u32,
+    tick: u32,
+    last_send: f32,
+    send_failed: AtomicBool, // Warned about a failed send already
+}
+
+impl NetHost {
+    fn send(&self, addr: SocketAddr, message: &HostMessage) {
+        if let Ok(bytes) = serde_json::to_vec(message) {
+            let result = self.socket.send_to(&bytes, addr);
+            warn_send_failure(result, &self.send_failed);
+        }
+    }
+}
+
+/// Log the first failed send of a socket (later ones would just repeat it)
+fn warn_send_failure(result: std::io::Result<usize>, failed: &AtomicBool) {
+    if let Err(e) = result
+        && !failed.swap(true, Ordering::Relaxed)
+    {
+        warn!("LAN send failed: {}", e);
+    }
+}
+
+/// Snapshot parts received so far for one tick (client)
+struct PendingSnapshot {
+    tick: u32,
+    parts: u16,
+    received: HashSet<u16>,
+    alive: HashSet<u32>, // Speaki ids seen in the received parts
+}
+
+/// Client side of a LAN session
+#[derive(Resource)]
+pub struct NetClient {
+    socket: UdpSocket,
+    host: SocketAddr,
+    user: Option<u32>,
+    last_tick: Option<u32>,
+    pending: Option<PendingSnapshot>,
+    mirrors: HashMap<u32, Entity>,
+    cursors: HashMap<u32, Vec2>,
+    holding: Option<u32>,
+    history: DragHistory,
+    last_send: f32,
+    last_hello: f32,
+    send_failed: AtomicBool, // Warned about a failed send already
+}
+
+impl NetClient {
+    fn send(&self, message: &ClientMessage) {
+        if let Ok(bytes) = serde_json::to_vec(message) {
+            let result = self.socket.send_to(&bytes, self.host);
+            warn_send_failure(result, &self.send_failed);
+        }
+    }
+}
+
+/// Open the host or client socket depending on the command line
+pub fn setup_net_session(mut commands: Commands, args: Res<NetArgs>) {
+    let bind_addr = match args.mode {
+        NetMode::Offline => return,
+        NetMode::Host => args.address,
+        NetMode::Client => SocketAddr::from(([0, 0, 0, 0], 0)),
+    };
+
+    let socket = match UdpSocket::bind(bind_addr).and_then(|s| {
+        s.set_nonblocking(true)?;
+        Ok(s)
+    }) {
+        Ok(socket) => socket,
+        Err(e) => {
+            warn!("LAN session disabled, failed to bind {}: {}", bind_addr, e);
+            commands.insert_resource(NetMode::Offline);
+            return;
+        }
+    };
+
+    match args.mode {
+        NetMode::Host => {
+            info!("Hosting LAN box on {}", bind_addr);
+            commands.insert_resource(NetHost {
+                socket,
+                clients: HashMap::new(),
+                next_user: HOST_USER + 1,
+                next_id: 0,
+                tick: 0,
+                last_send: 0.0,
+                send_failed: AtomicBool::new(false),
+            });
+        }
+        NetMode::Client => {
+            info!("Joining LAN box at {}", args.address);
+            commands.insert_resource(NetClient {
+                socket,
+                host: args.address,
+                user: None,
+                last_tick: None,
+                pending: None,
+                mirrors: HashMap::new(),
+                cursors: HashMap::new(),
+                holding: None,
+                history: DragHistory::default(),
+                last_send: 0.0,
+                last_hello: f32::NEG_INFINITY,
+                send_failed: AtomicBool::new(false),
+            });
+        }
+        NetMode::Offline => {}
+    }
+}
+
+/// Give every speaki a network id (host)
+pub fn net_assign_ids_system(
+    mut commands: Commands,
+    mut host: ResMut<NetHost>,
+    query: Query<Entity, (With<Speaki>, Without<NetId>)>,
+) {
+    for entity in query.iter() {
+        commands.entity(entity).insert(NetId(host.next_id));
+        host.next_id = host.next_id.wrapping_add(1);
+    }
+}
+
+/// Speaki state a client can grab, throw or remove
+type RemoteTarget = (
+    Entity,
+    &'static NetId,
+    &'static mut Transform,
+    &'static mut Velocity,
+    &'static mut RotationState,
+    Has<Dragged>,
+);
+
+/// What client input changes on the host
+#[derive(SystemParam)]
+pub struct RemoteActions<'w, 's> {
+    commands: Commands<'w, 's>,
+    speaki_query: Query<'w, 's, RemoteTarget, With<Speaki>>,
+    z_order: ResMut<'w, ZOrder>,
+    spawn_events: MessageWriter<'w, SpawnSpeakiEvent>,
+    despawn_events: MessageWriter<'w, DespawnSpeakiEvent>,
+    physics: Res<'w, PhysicsConfig>,
+}
+
+/// Handle input messages from clients (host)
+pub fn host_receive_system(
+    mut host: ResMut<NetHost>,
+    actions: RemoteActions,
+    mut voices: Voices,
+    window: Single<&Window>,
+    border: Res<BorderConfig>,
+    time: Res<Time>,
+) {
+    let RemoteActions {
+        mut commands,
+        mut speaki_query,
+        mut z_order,
+        mut spawn_events,
+        mut despawn_events,
+        physics,
+    } = actions;
+    let current_time = time.elapsed_secs();
+    let bounds = border.bounds(window.width(), window.height());
+    let mut buf = vec![0u8; MAX_DATAGRAM];
+
+    while let Ok((len, from)) = host.socket.recv_from(&mut buf) {
+        let Some(message) = serde_json::from_slice::<ClientMessage>(&buf[..len])
+            .ok()
+            .and_then(|message| message.sanitized(bounds))
+        else {
+            continue;
+        };
+
+        if let ClientMessage::Hello = message {
+            let user = match host.clients.get(&from) {
+                Some(remote) => remote.user,
+                None => {
+                    let user = host.next_user;
+                    host.next_user += 1;
+                    host.clients.insert(
+                        from,
+                        RemoteUser {
+                            user,
+                            cursor: Vec2::ZERO,
+                            last_seen: current_time,
+                            held: None,
+                        },
+                    );
+                    info!("User {} joined from {}", user, from);
+                    user
+                }
+            };
+            host.send(from, &HostMessage::Welcome { user });
+            continue;
+        }
+
+        // Everything else requires a hello first
+        let Some(remote) = host.clients.get_mut(&from) else {
+            continue;
+        };
+        remote.last_seen = current_time;
+
+        match message {
+            ClientMessage::Hello => {}
+            ClientMessage::Cursor { x, y } => {
+                remote.cursor = Vec2::new(x, y);
+            }
+            ClientMessage::Grab { id } => {
+                if remote.held.is_some() {
+                    continue;
+                }
+
+                let target = speaki_query
+                    .iter()
+                    .find(|(_, net_id, _, _, _, held)| net_id.0 == id && !held)
+                    .map(|(entity, ..)| entity);
+
+                if let Some(entity) = target {
+                    remote.held = Some(entity);
+
+                    // Bring to front
+                    if let Ok((_, _, mut transform, ..)) = speaki_query.get_mut(entity) {
+                        transform.translation.z = z_order.next();
+                    }
+                    commands.entity(entity).insert((Dragged, HeldBy(remote.user)));
+
+                    if let Some(idx) = voices.groups.pick(VoiceCategory::Drag) {
+                        voices.events.write(PlayVoiceEvent {
+                            entity: Some(entity),
+                            voice_index: idx,
+                            volume: voices.config.grab_volume,
+                            kind: VoiceKind::Grab,
+                        });
+                    }
+                }
+            }
+            ClientMessage::Release { vx, vy, curvature } => {
+                if let Some(entity) = remote.held.take() {
+                    let (throw_vel, spin) = physics.throw_from(Vec2::new(vx, vy), curvature);
+                    if let Ok((_, _, _, mut vel, mut rot, _)) = speaki_query.get_mut(entity) {
+                        vel.x = throw_vel.x;
+                        vel.y = throw_vel.y;
+                        if spin.abs() > THROW_SPIN_THRESHOLD {
+                            rot.speed = spin;
+                        }
+                    }
+                    commands.entity(entity).remove::<(Dragged, HeldBy)>();
+                }
+            }
+            ClientMessage::Spawn { x, y } => {
+                spawn_events.write(SpawnSpeakiEvent {
+                    position: Vec2::new(x, y),
+                    velocity: Vec2::ZERO,
+                });
+
+                if let Some(idx) = voices.groups.pick(VoiceCategory::Create) {
+                    voices.events.write(PlayVoiceEvent {
+                        entity: None,
+                        voice_index: idx,
+                        volume: voices.config.create_volume,
+                        kind: VoiceKind::Create,
+                    });
+                }
+            }
+            ClientMessage::Remove { id } => {
+                let target = speaki_query
+                    .iter()
+                    .find(|(_, net_id, ..)| net_id.0 == id)
+                    .map(|(entity, ..)| entity);
+
+                if let Some(entity) = target {
+                    despawn_events.write(DespawnSpeakiEvent { entity });
+
+                    if let Some(idx) = voices.groups.pick(VoiceCategory::Remove) {
+                        voices.events.write(PlayVoiceEvent {
+                            entity: Some(entity),
+                            voice_index: idx,
+                            volume: voices.config.remove_volume,
+                            kind: VoiceKind::Remove,
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    // Drop silent clients and let go of what they were holding
+    host.clients.retain(|addr, remote| {
+        if current_time - remote.last_seen < CLIENT_TIMEOUT {
+            return true;
+        }
+
+        info!("User {} ({}) timed out", remote.user, addr);
+        if let Some(entity) = remote.held
+            && speaki_query.contains(entity)
+        {
+            commands.entity(entity).remove::<(Dragged, HeldBy)>();
+        }
+        false
+    });
+
+    // Forget holds on speakis that no longer exist (removed or merged)
+    for remote in host.clients.values_mut() {
+        if remote.held.is_some_and(|e| !speaki_query.contains(e)) {
+            remote.held = None;
+        }
+    }
+}
+
+/// Move speakis held by remote users to their cursors (host)
+pub fn host_remote_drag_system(
+    host: Res<NetHost>,
+    mut query: Query<(&mut Transform, &mut Velocity), With<Speaki>>,
+) {
+    for remote in host.clients.values() {
+        let Some(entity) = remote.held else {
+            continue;
+        };
+
+        if let Ok((mut transform, mut vel)) = query.get_mut(entity) {
+            transform.translation.x = remote.cursor.x;
+            transform.translation.y = remote.cursor.y;
+            vel.x = 0.0;
+            vel.y = 0.0;
+        }
+    }
+}
+
+/// Speaki state the host sends in snapshots
+type SnapshotSource = (
+    &'static NetId,
+    &'static Transform,
+    &'static SpeakiSize,
+    &'static SpriteState,
+    Option<&'static Shiny>,
+    Option<&'static HeldBy>,
+    Has<Dragged>,
+);
+
+/// Send the authoritative state to every client (host)
+pub fn host_broadcast_system(
+    mut host: ResMut<NetHost>,
+    window: Single<&Window>,
+    camera_q: Query<(&Camera, &GlobalTransform)>,
+    speaki_query: Query<SnapshotSource, With<Speaki>>,
+    time: Res<Time>,
+) {
+    let current_time = time.elapsed_secs();
+    if current_time - host.last_send < SEND_INTERVAL || host.clients.is_empty() {
+        return;
+    }
+    host.last_send = current_time;
+    host.tick = host.tick.wrapping_add(1);
+
+    let speakis: Vec<SpeakiSnapshot> = speaki_query
+        .iter()
+        .map(
+            |(net_id, transform, size, sprite_state, shiny, held_by, dragged)| SpeakiSnapshot {
+                id: net_id.0,
+                position: transform.translation.to_array(),
+                angle: transform.rotation.to_euler(EulerRot::XYZ).2,
+                size: size.0,
+                sprite_index: sprite_state.current_index,
+                shiny_color: shiny.map(|s| {
+                    let c = s.base_color.to_srgba();
+                    [c.red, c.green, c.blue]
+                }),
+                held_by: held_by
+                    .map(|h| h.0)
+                    .or(dragged.then_some(HOST_USER)),
+            },
+        )
+        .collect();
+
+    let mut cursors: Vec<CursorSnapshot> = host
+        .clients
+        .values()
+        .map(|remote| CursorSnapshot {
+            user: remote.user,
+            position: remote.cursor.to_array(),
+        })
+        .collect();
+
+    // Include the host's own cursor
+    if let Ok((camera, camera_transform)) = camera_q.single()
+        && let Some(cursor_pos) = window
+            .cursor_position()
+            .and_then(|p| camera.viewport_to_world_2d(camera_transform, p).ok())
+    {
+        cursors.push(CursorSnapshot {
+            user: HOST_USER,
+            position: cursor_pos.to_array(),
+        });
+    }
+
+    // Split into MTU-sized packets (always at least one, for the cursors)
+    let chunks: Vec<&[SpeakiSnapshot]> = if speakis.is_empty() {
+        vec![&[]]
+    } else {
+        speakis.chunks(SPEAKIS_PER_PACKET).collect()
+    };
+    let parts = chunks.len().min(u16::MAX as usize) as u16;
+
+    for (part, chunk) in chunks.into_iter().take(parts as usize).enumerate() {
+        let snapshot = HostMessage::Snapshot {
+            tick: host.tick,
+            part: part as u16,
+            parts,
+            speakis: chunk.to_vec(),
+            cursors: if part == 0 {
+                std::mem::take(&mut cursors)
+            } else {
+                Vec::new()
+            },
+        };
+
+        for &addr in host.clients.keys() {
+            host.send(addr, &snapshot);
+        }
+    }
+}
+
+/// Show cursor markers for connected clients (host)
+pub fn host_cursor_marker_system(
+    mut commands: Commands,
+    host: Res<NetHost>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut markers: Query<(Entity, &RemoteCursor, &mut Transform)>,
+) {
+    let cursors: HashMap<u32, Vec2> = host
+        .clients
+        .values()
+        .map(|remote| (remote.user, remote.cursor))
+        .collect();
+
+    update_cursor_markers(
+        &mut commands,
+        &mut meshes,
+        &mut materials,
+        &mut markers,
+        &cursors,
+    );
+}
+
+/// Mirror speaki state a snapshot overwrites (client)
+type MirrorTarget = (
+    &'static mut Transform,
+    &'static mut SpeakiSize,
+    &'static mut SpriteState,
+    Option<&'static mut Sprite>,
+    Has<Dragged>,
+);
+
+/// Apply snapshots from the host to local mirror speakis (client)
+pub fn client_receive_system(
+    mut commands: Commands,
+    mut client: ResMut<NetClient>,
+    mut query: Query<MirrorTarget, With<Speaki>>,
+    sprites: Res<SpriteAssets>,
+    shiny_config: Res<ShinyConfig>,
+    time: Res<Time>,
+) {
+    let current_time = time.elapsed_secs();
+
+    // Keep saying hello until the host answers
+    if client.user.is_none() && current_time - client.last_hello > HELLO_INTERVAL {
+        client.last_hello = current_time;
+        client.send(&ClientMessage::Hello);
+    }
+
+    let mut buf = vec![0u8; MAX_DATAGRAM];
+
+    while let Ok((len, from)) = client.socket.recv_from(&mut buf) {
+        if from != client.host {
+            continue;
+        }
+
+        let Ok(message) = serde_json::from_slice::<HostMessage>(&buf[..len]) else {
+            continue;
+        };
+
+        match message {
+            HostMessage::Welcome { user } => {
+                info!("Joined LAN box as user {}", user);
+                client.user = Some(user);
+            }
+            HostMessage::Snapshot {
+                tick,
+                part,
+                parts,
+                speakis,
+                cursors,
+            } => {
+                // Ignore snapshots that arrive out of order
+                if client
+                    .last_tick
+                    .is_some_and(|last| tick.wrapping_sub(last) > u32::MAX / 2)
+                {
+                    continue;
+                }
+                client.last_tick = Some(tick);
+
+                // A newer tick drops the parts of an unfinished one
+                if client.pending.as_ref().is_none_or(|p| p.tick != tick) {
+                    client.pending = Some(PendingSnapshot {
+                        tick,
+                        parts,
+                        received: HashSet::new(),
+                        alive: HashSet::new(),
+                    });
+                }
+                let Some(mut pending) = client.pending.take() else {
+                    continue;
+                };
+                pending.received.insert(part);
+
+                for speaki in speakis {
+                    pending.alive.insert(speaki.id);
+                    let position = Vec3::from(speaki.position);
+
+                    let Some(&entity) = client.mirrors.get(&speaki.id) else {
+                        let entity = spawn_speaki_with(
+                            &mut commands,
+                            position.truncate(),
+                            Vec2::ZERO,
+                            speaki.size,
+                            speaki.shiny_color.map(|[r, g, b]| Color::srgb(r, g, b)),
+                            &sprites,
+                            &shiny_config,
+                        );
+                        // Mirrors are animated by the host, not locally
+                        commands
+                            .entity(entity)
+                            .insert(NetId(speaki.id))
+                            .remove::<(BlinkTimer, IdleVoiceTimer)>();
+                        client.mirrors.insert(speaki.id, entity);
+                        continue;
+                    };
+
+                    let Ok((mut transform, mut size, mut sprite_state, sprite, dragged)) =
+                        query.get_mut(entity)
+                    else {
+                        continue;
+                    };
+
+                    transform.translation = position;
+                    transform.rotation = Quat::from_rotation_z(speaki.angle);
+
+                    if sprite_state.current_index != speaki.sprite_index {
+                        sprite_state.current_index = speaki.sprite_index;
+                    }
+
+                    if size.0 != speaki.size {
+                        size.0 = speaki.size;
+                        if let Some(mut sprite) = sprite {
+                            sprite.custom_size = Some(Vec2::splat(speaki.size));
+                        }
+                    }
+
+                    // Mirror drag ownership
+                    match (speaki.held_by.is_some(), dragged) {
+                        (true, false) => {
+                            commands.entity(entity).insert(Dragged);
+                        }
+                        (false, true) => {
+                            commands.entity(entity).remove::<Dragged>();
+                        }
+                        _ => {}
+                    }
+                }
+
+                if part == 0 {
+                    let own_user = client.user;
+                    client.cursors = cursors
+                        .into_iter()
+                        .filter(|c| Some(c.user) != own_user)
+                        .map(|c| (c.user, Vec2::from(c.position)))
+                        .collect();
+                }
+
+                // Once every part is in, despawn mirrors the host no longer has
+                if pending.received.len() < pending.parts as usize {
+                    client.pending = Some(pending);
+                    continue;
+                }
+                client.mirrors.retain(|id, entity| {
+                    let keep = pending.alive.contains(id);
+                    if !keep {
+                        commands.entity(*entity).despawn();
+                    }
+                    keep
+                });
+            }
+        }
+    }
+}
+
+/// Local mouse and keyboard state read by `client_input_system`
+#[derive(SystemParam)]
+pub struct ClientPointer<'w, 's> {
+    press_events: MessageReader<'w, 's, Pointer<Press>>,
+    mouse_button: Res<'w, ButtonInput<MouseButton>>,
+    keyboard: Res<'w, ButtonInput<KeyCode>>,
+    key_bindings: Res<'w, KeyBindings>,
+    window: Single<'w, 's, &'static Window>,
+    camera_q: Query<'w, 's, (&'static Camera, &'static GlobalTransform)>,
+}
+
+/// Send local mouse input to the host (client)
+pub fn client_input_system(
+    mut client: ResMut<NetClient>,
+    pointer: ClientPointer,
+    speaki_query: Query<(&NetId, Has<Dragged>), With<Speaki>>,
+    config: Res<GameConfig>,
+    mut voices: Voices,
+    time: Res<Time>,
+) {
+    let ClientPointer {
+        mut press_events,
+        mouse_button,
+        keyboard,
+        key_bindings,
+        window,
+        camera_q,
+    } = pointer;
+
+    if client.user.is_none() {
+        return;
+    }
+
+    let Ok((camera, camera_transform)) = camera_q.single() else {
+        return;
+    };
+
+    let Some(cursor_pos) = window
+        .cursor_position()
+        .and_then(|p| camera.viewport_to_world_2d(camera_transform, p).ok())
+    else {
+        return;
+    };
+
+    let current_time = time.elapsed_secs();
+
+    // Cursor updates double as keep-alive
+    if current_time - client.last_send > SEND_INTERVAL {
+        client.last_send = current_time;
+        client.send(&ClientMessage::Cursor {
+            x: cursor_pos.x,
+            y: cursor_pos.y,
+        });
+    }
+
+    let window_drag = key_bindings.pressed(KeyAction::WindowDrag, &keyboard);
+
+    for press in press_events.read() {
+        if press.pointer_id != PointerId::Mouse {
+            continue;
+        }
+
+        let entity = press.entity;
+        let hit = speaki_query.get(entity).ok();
+
+        match press.event.button {
+            PointerButton::Primary if !window_drag => match hit {
+                Some((net_id, held)) => {
+                    if held {
+                        continue;
+                    }
+
+                    client.holding = Some(net_id.0);
+                    client.history.start(current_time, cursor_pos);
+                    client.send(&ClientMessage::Grab { id: net_id.0 });
+
+                    if let Some(idx) = voices.groups.pick(VoiceCategory::Drag) {
+                        voices.events.write(PlayVoiceEvent {
+                            entity: Some(entity),
+                            voice_index: idx,
+                            volume: voices.config.grab_volume,
+                            kind: VoiceKind::Grab,
+                        });
+                    }
+                }
+                None if config.click_to_add => {
+                    client.send(&ClientMessage::Spawn {
+                        x: cursor_pos.x,
+                        y: cursor_pos.y,
+                    });
+
+                    if let Some(idx) = voices.groups.pick(VoiceCategory::Create) {
+                        voices.events.write(PlayVoiceEvent {
+                            entity: None,
+                            voice_index: idx,
+                            volume: voices.config.create_volume,
+                            kind: VoiceKind::Create,
+                        });
+                    }
+                }
+                None => {}
+            },
+            PointerButton::Secondary => {
+                let Some((net_id, _)) = hit else {
+                    continue;
+                };
+
+                client.send(&ClientMessage::Remove { id: net_id.0 });
+
+                if let Some(idx) = voices.groups.pick(VoiceCategory::Remove) {
+                    voices.events.write(PlayVoiceEvent {
+                        entity: Some(entity),
+                        voice_index: idx,
+                        volume: voices.config.remove_volume,
+                        kind: VoiceKind::Remove,
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if client.holding.is_some() {
+        client.history.push(current_time, cursor_pos);
+
+        if mouse_button.just_released(MouseButton::Left) {
+            let throw_vel = client.history.velocity(current_time);
+            let curvature = client.history.curvature(current_time);
+
+            client.holding = None;
+            client.send(&ClientMessage::Release {
+                vx: throw_vel.x,
+                vy: throw_vel.y,
+                curvature,
+            });
+        }
+    }
+}
+
+/// Show cursor markers for the host and other clients (client)
+pub fn client_cursor_marker_system(
+    mut commands: Commands,
+    client: Res<NetClient>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut markers: Query<(Entity, &RemoteCursor, &mut Transform)>,
+) {
+    update_cursor_markers(
+        &mut commands,
+        &mut meshes,
+        &mut materials,
+        &mut markers,
+        &client.cursors,
+    );
+}
+
+/// Spawn, move and despawn cursor markers to match the given cursors
+fn update_cursor_markers(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<ColorMaterial>,
+    markers: &mut Query<(Entity, &RemoteCursor, &mut Transform)>,
+    cursors: &HashMap<u32, Vec2>,
+) {
+    let mut shown = HashSet::new();
+
+    for (entity, marker, mut transform) in markers.iter_mut() {
+        match cursors.get(&marker.user) {
+            Some(pos) => {
+                transform.translation = pos.extend(CURSOR_Z);
+                shown.insert(marker.user);
+            }
+            None => commands.entity(entity).despawn(),
+        }
+    }
+
+    for (&user, pos) in cursors {
+        if shown.contains(&user) {
+            continue;
+        }
+
+        // Distinct color per user (golden angle hue steps)
+        let color = Color::hsl((user as f32 * 137.5) % 360.0, 0.8, 0.6);
+        commands.spawn((
+            RemoteCursor { user },
+            Mesh2d(meshes.add(Circle::new(8.0))),
+            MeshMaterial2d(materials.add(ColorMaterial::from_color(color))),
+            Transform::from_translation(pos.extend(CURSOR_Z)),
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> NetArgs {
+        let args: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+        NetArgs::from_args(&args)
+    }
+
+    #[test]
+    fn no_args_stays_offline() {
+        let args = parse(&["speaki-box"]);
+        assert_eq!(args.mode, NetMode::Offline);
+    }
+
+    #[test]
+    fn host_uses_default_port() {
+        let args = parse(&["speaki-box", "--host"]);
+        assert_eq!(args.mode, NetMode::Host);
+        assert_eq!(args.address.port(), DEFAULT_NET_PORT);
+    }
+
+    #[test]
+    fn host_takes_port() {
+        let args = parse(&["speaki-box", "--host", "5000"]);
+        assert_eq!(args.mode, NetMode::Host);
+        assert_eq!(args.address.port(), 5000);
+    }
+
+    #[test]
+    fn host_leaves_other_args_alone() {
+        let args = parse(&["speaki-box", "--host", "--fullscreen"]);
+        assert_eq!(args.mode, NetMode::Host);
+        assert_eq!(args.address.port(), DEFAULT_NET_PORT);
+    }
+
+    #[test]
+    fn join_with_port() {
+        let args = parse(&["speaki-box", "--join", "127.0.0.1:5000"]);
+        assert_eq!(args.mode, NetMode::Client);
+        assert_eq!(args.address, SocketAddr::from(([127, 0, 0, 1], 5000)));
+    }
+
+    #[test]
+    fn join_without_port_uses_default() {
+        let args = parse(&["speaki-box", "--join", "127.0.0.1"]);
+        assert_eq!(args.mode, NetMode::Client);
+        assert_eq!(
+            args.address,
+            SocketAddr::from(([127, 0, 0, 1], DEFAULT_NET_PORT))
+        );
+    }
+
+    #[test]
+    fn join_without_address_stays_offline() {
+        let args = parse(&["speaki-box", "--join"]);
+        assert_eq!(args.mode, NetMode::Offline);
+    }
+
+    fn bounds() -> Rect {
+        Rect::new(-400.0, -300.0, 400.0, 300.0)
+    }
+
+    #[test]
+    fn client_message_rejects_non_finite_values() {
+        let message = ClientMessage::Cursor {
+            x: f32::INFINITY,
+            y: 0.0,
+        };
+        assert!(message.sanitized(bounds()).is_none());
+
+        let message = ClientMessage::Spawn { x: 0.0, y: f32::NAN };
+        assert!(message.sanitized(bounds()).is_none());
+
+        // Too big for an f32, so it parses as infinity
+        let message: ClientMessage =
+            serde_json::from_str(r#"{"type":"cursor","x":1e39,"y":0}"#).unwrap();
+        assert!(message.sanitized(bounds()).is_none());
+    }
+
+    #[test]
+    fn broken_release_drops_without_throwing() {
+        let message: ClientMessage =
+            serde_json::from_str(r#"{"type":"release","vx":1e39,"vy":5,"curvature":0}"#).unwrap();
+        let Some(ClientMessage::Release { vx, vy, curvature }) = message.sanitized(bounds()) else {
+            panic!("release rejected");
+        };
+        assert_eq!((vx, vy, curvature), (0.0, 0.0, 0.0));
+
+        let message = ClientMessage::Release {
+            vx: 3.0,
+            vy: -4.0,
+            curvature: 0.5,
+        };
+        let Some(ClientMessage::Release { vx, vy, curvature }) = message.sanitized(bounds()) else {
+            panic!("release rejected");
+        };
+        assert_eq!((vx, vy, curvature), (3.0, -4.0, 0.5));
+    }
+
+    #[test]
+    fn client_positions_are_clamped_to_the_box() {
+        let message = ClientMessage::Cursor {
+            x: 1e9,
+            y: -1e9,
+        };
+        let Some(ClientMessage::Cursor { x, y }) = message.sanitized(bounds()) else {
+            panic!("cursor rejected");
+        };
+        assert_eq!((x, y), (400.0, -300.0));
+
+        let message = ClientMessage::Spawn { x: -500.0, y: 10.0 };
+        let Some(ClientMessage::Spawn { x, y }) = message.sanitized(bounds()) else {
+            panic!("spawn rejected");
+        };
+        assert_eq!((x, y), (-400.0, 10.0));
+    }
+}
@@ -1,3 +1,6 @@
+use std::collections::HashSet;
+
+use bevy::ecs::schedule::ScheduleLabel;
 use bevy::prelude::*;
 use bevy::window::WindowMoved;
 
@@ -5,22 +8,62 @@ use crate::components::*;
 use crate::events::*;
 use crate::resources::*;
 
-/// Apply gravity to all non-dragged speakis
+/// One simulation step (physics is per frame at 1x); run 0..n times a frame for pause/slow/fast
+#[derive(ScheduleLabel, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SimulationStep;
+
+/// Run as many simulation steps as the speed setting allows this frame
+pub fn simulation_step_system(world: &mut World) {
+    let dt = world.resource::<Time>().delta_secs();
+    let steps = world.resource_mut::<SimulationSpeed>().advance(dt);
+    for _ in 0..steps {
+        world.run_schedule(SimulationStep);
+    }
+}
+
+/// Speakis the simulation moves (held ones follow the cursor, pinned ones stay put)
+type FreeSpeaki = (With<Speaki>, Without<Dragged>, Without<Pinned>);
+/// Speakis held by the mouse, a finger, the gamepad or a remote user
+type HeldSpeaki = (With<Speaki>, With<Dragged>);
+/// Speakis that turn with the simulation (pinned ones keep spinning in place)
+type UnheldSpeaki = (With<Speaki>, Without<Dragged>);
+
+/// Apply gravity to all non-dragged, unpinned speakis (along the possibly tilted direction)
 pub fn gravity_system(
-    mut query: Query<&mut Velocity, (With<Speaki>, Without<Dragged>)>,
+    mut query: Query<&mut Velocity, FreeSpeaki>,
     physics: Res<PhysicsConfig>,
+    sim_speed: Res<SimulationSpeed>,
 ) {
+    let gravity = physics.gravity * sim_speed.step_scale;
     for mut vel in query.iter_mut() {
-        // Bevy Y is up, so gravity decreases Y
-        vel.y -= physics.gravity;
+        // Bevy Y is up, so untilted gravity decreases Y
+        vel.x += physics.gravity_direction.x * gravity;
+        vel.y += physics.gravity_direction.y * gravity;
     }
 }
 
-/// Update positions based on velocity
-pub fn movement_system(mut query: Query<(&mut Transform, &Velocity), With<Speaki>>) {
+/// Update positions based on velocity (held speakis move in `held_movement_system`)
+pub fn movement_system(
+    mut query: Query<(&mut Transform, &Velocity), FreeSpeaki>,
+    sim_speed: Res<SimulationSpeed>,
+) {
+    let scale = sim_speed.step_scale;
     for (mut transform, vel) in query.iter_mut() {
+        transform.translation.x += vel.x * scale;
+        transform.translation.y += vel.y * scale;
+    }
+}
+
+/// Move and turn held speakis once per frame, outside the simulation step, so
+/// spring dragging still follows the cursor while paused and feels the same at any speed
+pub fn held_movement_system(
+    mut query: Query<(&mut Transform, &Velocity, &RotationState), HeldSpeaki>,
+    physics: Res<PhysicsConfig>,
+) {
+    for (mut transform, vel, rot) in query.iter_mut() {
         transform.translation.x += vel.x;
         transform.translation.y += vel.y;
+        transform.rotate_z(rot.speed * physics.rotation_speed);
     }
 }
 
@@ -40,15 +83,14 @@ pub fn wall_collision_system(
     physics: Res<PhysicsConfig>,
     border: Res<BorderConfig>,
     mut bounce_events: MessageWriter<WallBounceEvent>,
+    mut exit_events: MessageWriter<PortalExitEvent>,
 ) {
-    let half_width = window.width() / 2.0;
-    let half_height = window.height() / 2.0;
-
     // Calculate bounds
-    let left_bound = -half_width + half_width * border.left;
-    let right_bound = half_width - half_width * border.right;
-    let top_bound = half_height - half_height * border.up;
-    let bottom_bound = -half_height + half_height * border.down;
+    let bounds = border.bounds(window.width(), window.height());
+    let left_bound = bounds.min.x;
+    let right_bound = bounds.max.x;
+    let top_bound = bounds.max.y;
+    let bottom_bound = bounds.min.y;
 
     let bounce_threshold = 1000.0 * physics.bounce_responsiveness;
 
@@ -58,8 +100,17 @@ pub fn wall_collision_system(
         let should_update_rotation = rot.speed.abs() < 0.01 && vel_sq > 100.0;
 
         // Bottom collision
-        if transform.translation.y - half_size < bottom_bound {
+        if border.portal_down {
+            // Open edge: leave once fully outside
+            if transform.translation.y + half_size < bottom_bound {
+                exit_events.write(PortalExitEvent {
+                    entity,
+                    edge: PortalEdge::Bottom,
+                });
+            }
+        } else if transform.translation.y - half_size < bottom_bound {
             transform.translation.y = bottom_bound + half_size;
+            let impact = vel.y.abs(); // Speed into the wall
             vel.y *= -physics.bounce;
             vel.x *= physics.friction;
             rot.speed *= physics.friction;
@@ -73,13 +124,25 @@ pub fn wall_collision_system(
             }
 
             if vel_sq > bounce_threshold {
-                bounce_events.write(WallBounceEvent { entity });
+                bounce_events.write(WallBounceEvent {
+                    entity,
+                    speed: impact,
+                    normal: Vec2::Y,
+                });
             }
         }
 
         // Top collision
-        if transform.translation.y + half_size > top_bound {
+        if border.portal_up {
+            if transform.translation.y - half_size > top_bound {
+                exit_events.write(PortalExitEvent {
+                    entity,
+                    edge: PortalEdge::Top,
+                });
+            }
+        } else if transform.translation.y + half_size > top_bound {
             transform.translation.y = top_bound - half_size;
+            let impact = vel.y.abs();
             vel.y *= -physics.bounce;
             vel.x *= physics.friction;
             rot.speed *= physics.friction;
@@ -89,13 +152,25 @@ pub fn wall_collision_system(
             }
 
             if vel_sq > bounce_threshold {
-                bounce_events.write(WallBounceEvent { entity });
+                bounce_events.write(WallBounceEvent {
+                    entity,
+                    speed: impact,
+                    normal: Vec2::NEG_Y,
+                });
             }
         }
 
         // Left collision
-        if transform.translation.x - half_size < left_bound {
+        if border.portal_left {
+            if transform.translation.x + half_size < left_bound {
+                exit_events.write(PortalExitEvent {
+                    entity,
+                    edge: PortalEdge::Left,
+                });
+            }
+        } else if transform.translation.x - half_size < left_bound {
             transform.translation.x = left_bound + half_size;
+            let impact = vel.x.abs();
             vel.x *= -physics.bounce;
             vel.y *= physics.friction;
             rot.speed *= physics.friction;
@@ -109,13 +184,25 @@ pub fn wall_collision_system(
             }
 
             if vel_sq > bounce_threshold {
-                bounce_events.write(WallBounceEvent { entity });
+                bounce_events.write(WallBounceEvent {
+                    entity,
+                    speed: impact,
+                    normal: Vec2::X,
+                });
             }
         }
 
         // Right collision
-        if transform.translation.x + half_size > right_bound {
+        if border.portal_right {
+            if transform.translation.x - half_size > right_bound {
+                exit_events.write(PortalExitEvent {
+                    entity,
+                    edge: PortalEdge::Right,
+                });
+            }
+        } else if transform.translation.x + half_size > right_bound {
             transform.translation.x = right_bound - half_size;
+            let impact = vel.x.abs();
             vel.x *= -physics.bounce;
             vel.y *= physics.friction;
             rot.speed *= physics.friction;
@@ -125,7 +212,11 @@ pub fn wall_collision_system(
             }
 
             if vel_sq > bounce_threshold {
-                bounce_events.write(WallBounceEvent { entity });
+                bounce_events.write(WallBounceEvent {
+                    entity,
+                    speed: impact,
+                    normal: Vec2::NEG_X,
+                });
             }
         }
     }
@@ -144,18 +235,41 @@ pub fn speaki_collision_system(
         With<Speaki>,
     >,
     dragged_query: Query<Entity, With<Dragged>>,
+    pinned_query: Query<(), (With<Pinned>, Without<Dragged>)>,
     physics: Res<PhysicsConfig>,
+    sim_speed: Res<SimulationSpeed>,
+    mut collision_events: MessageWriter<SpeakiCollisionEvent>,
 ) {
     if !physics.collision_enabled {
         return;
     }
 
-    let dragged_entity = dragged_query.iter().next();
+    // Spin damping per step, shortened steps damp proportionally less
+    let spin_damping = physics.collision_damping.powf(sim_speed.step_scale);
+
+    // Same speed a wall bounce needs to make a sound
+    let bounce_threshold = 1000.0 * physics.bounce_responsiveness;
+
+    // Every held speaki (whole group drags, several fingers) is kinematic;
+    // spring-dragged speakis are regular bodies that push and get pushed
+    let dragged: HashSet<Entity> = if physics.spring_drag {
+        HashSet::new()
+    } else {
+        dragged_query.iter().collect()
+    };
 
     // Collect all speaki data
     let mut speakis: Vec<(Entity, Vec3, Vec2, f32)> = query
         .iter()
-        .map(|(e, t, v, _, s)| (e, t.translation, Vec2::new(v.x, v.y), s.0))
+        .map(|(e, t, v, _, s)| {
+            // Pinned speakis act as static obstacles
+            let vel = if pinned_query.contains(e) {
+                Vec2::ZERO
+            } else {
+                Vec2::new(v.x, v.y)
+            };
+            (e, t.translation, vel, s.0)
+        })
         .collect();
 
     let len = speakis.len();
@@ -178,19 +292,29 @@ pub fn speaki_collision_system(
                 let nx = dx / dist;
                 let ny = dy / dist;
 
-                // Separate the speakis
-                let sep = overlap * 0.5;
-                speakis[i].1.x -= nx * sep;
-                speakis[i].1.y -= ny * sep;
-                speakis[j].1.x += nx * sep;
-                speakis[j].1.y += ny * sep;
+                // Separate the speakis (pinned ones don't move, the other takes all of it)
+                let pinned1 = pinned_query.contains(e1);
+                let pinned2 = pinned_query.contains(e2);
+                let (sep1, sep2) = match (pinned1, pinned2) {
+                    (true, true) => (0.0, 0.0),
+                    (true, false) => (0.0, overlap),
+                    (false, true) => (overlap, 0.0),
+                    (false, false) => (overlap * 0.5, overlap * 0.5),
+                };
+                speakis[i].1.x -= nx * sep1;
+                speakis[i].1.y -= ny * sep1;
+                speakis[j].1.x += nx * sep2;
+                speakis[j].1.y += ny * sep2;
 
                 // Calculate relative velocity
                 let dvx = vel2.x - vel1.x;
                 let dvy = vel2.y - vel1.y;
 
+                let is_e1_dragged = dragged.contains(&e1);
+                let is_e2_dragged = dragged.contains(&e2);
+
                 // Apply cursor impulse if one is being dragged
-                let dvn = if Some(e1) == dragged_entity || Some(e2) == dragged_entity {
+                let dvn = if is_e1_dragged || is_e2_dragged {
                     -physics.cursor_impulse
                 } else {
                     dvx * nx + dvy * ny
@@ -201,19 +325,27 @@ pub fn speaki_collision_system(
                     continue;
                 }
 
+                // Sound uses the real closing speed, not the cursor impulse
+                let impact = -(dvx * nx + dvy * ny);
+                if impact * impact > bounce_threshold {
+                    collision_events.write(SpeakiCollisionEvent {
+                        entity1: e1,
+                        entity2: e2,
+                        speed: impact,
+                        normal: Vec2::new(nx, ny),
+                    });
+                }
+
                 // Calculate impulse (assuming equal mass)
                 let impulse = dvn;
 
                 // Update velocities
-                let is_e1_dragged = Some(e1) == dragged_entity;
-                let is_e2_dragged = Some(e2) == dragged_entity;
-
-                if !is_e1_dragged {
+                if !is_e1_dragged && !pinned1 {
                     speakis[i].2.x += impulse * nx * physics.collision_damping;
                     speakis[i].2.y += impulse * ny * physics.collision_damping;
                 }
 
-                if !is_e2_dragged {
+                if !is_e2_dragged && !pinned2 {
                     speakis[j].2.x -= impulse * nx * physics.collision_damping;
                     speakis[j].2.y -= impulse * ny * physics.collision_damping;
                 }
@@ -228,18 +360,19 @@ pub fn speaki_collision_system(
             transform.translation.y = new_pos.y;
             vel.x = new_vel.x;
             vel.y = new_vel.y;
-            rot.speed *= physics.collision_damping;
+            rot.speed *= spin_damping;
         }
     }
 }
 
 /// Update rotation
 pub fn rotation_system(
-    mut query: Query<(&mut Transform, &RotationState), With<Speaki>>,
+    mut query: Query<(&mut Transform, &RotationState), UnheldSpeaki>,
     physics: Res<PhysicsConfig>,
+    sim_speed: Res<SimulationSpeed>,
 ) {
     for (mut transform, rot) in query.iter_mut() {
-        let angle = rot.speed * physics.rotation_speed;
+        let angle = rot.speed * physics.rotation_speed * sim_speed.step_scale;
         transform.rotate_z(angle);
     }
 }
@@ -279,18 +412,18 @@ pub fn window_inertia_system(
 /// Shiny speakis periodically explode and push nearby speakis away
 pub fn shiny_explosion_system(
     mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
+    (mut meshes, mut materials): (ResMut<Assets<Mesh>>, ResMut<Assets<ColorMaterial>>),
     mut shiny_query: Query<(Entity, &Transform, &mut Shiny)>,
     mut speaki_query: Query<(Entity, &Transform, &mut Velocity), With<Speaki>>,
     shiny_config: Res<ShinyConfig>,
-    time: Res<Time>,
+    sim_speed: Res<SimulationSpeed>,
+    mut explosion_events: MessageWriter<ShinyExplosionEvent>,
 ) {
     if !shiny_config.explosion_enabled {
         return;
     }
 
-    let dt = time.delta_secs();
+    let dt = sim_speed.step_dt;
     let radius_sq = shiny_config.explosion_radius * shiny_config.explosion_radius;
 
     // Collect shiny positions that are exploding
@@ -311,6 +444,10 @@ pub fn shiny_explosion_system(
 
     // Apply explosion force to nearby speakis and spawn shockwave
     for (shiny_entity, shiny_pos, shiny_color) in explosions {
+        explosion_events.write(ShinyExplosionEvent {
+            entity: shiny_entity,
+        });
+
         // Spawn shockwave visual effect
         if shiny_config.shockwave_enabled {
             let base = shiny_color.to_srgba();
@@ -362,8 +499,9 @@ pub fn shockwave_animation_system(
     mut query: Query<(Entity, &mut Shockwave, &mut Transform, &MeshMaterial2d<ColorMaterial>)>,
     mut materials: ResMut<Assets<ColorMaterial>>,
     time: Res<Time>,
+    sim_speed: Res<SimulationSpeed>,
 ) {
-    let dt = time.delta_secs();
+    let dt = time.delta_secs() * sim_speed.factor();
 
     for (entity, mut shockwave, mut transform, material_handle) in query.iter_mut() {
         shockwave.elapsed += dt;
@@ -388,21 +526,21 @@ pub fn shockwave_animation_system(
     }
 }
 
+/// Speaki state a merge reads from both partners and grows on the survivor
+type MergeCandidate = (
+    Entity,
+    &'static Transform,
+    &'static mut Velocity,
+    &'static mut SpeakiSize,
+    &'static mut Sprite,
+    Option<&'static Shiny>,
+);
+
 /// Detect and handle speaki merging (Suika game style)
 /// When two speakis of the same size collide, they merge into one larger speaki
 pub fn speaki_merge_system(
     mut commands: Commands,
-    mut query: Query<
-        (
-            Entity,
-            &Transform,
-            &mut Velocity,
-            &mut SpeakiSize,
-            &mut Sprite,
-            Option<&Shiny>,
-        ),
-        With<Speaki>,
-    >,
+    mut query: Query<MergeCandidate, With<Speaki>>,
     dragged_query: Query<Entity, With<Dragged>>,
     merge_config: Res<MergeConfig>,
     mut merge_events: MessageWriter<MergeSpeakiEvent>,
@@ -420,7 +558,7 @@ pub fn speaki_merge_system(
         .collect();
 
     let len = speakis.len();
-    let mut to_merge: Vec<(Entity, Entity, Vec2, Vec2, f32, bool)> = Vec::new();
+    let mut to_merge: Vec<(Entity, Entity, Vec2, f32, bool)> = Vec::new();
     let mut already_merged: std::collections::HashSet<Entity> = std::collections::HashSet::new();
 
     // Check all pairs for merge candidates
@@ -462,13 +600,12 @@ pub fn speaki_merge_system(
             let min_dist_sq = min_dist * min_dist;
 
             if dist_sq < min_dist_sq && dist_sq > 0.0 {
-                // Merge! Use midpoint position and combined velocity
-                let mid_pos = Vec2::new((pos1.x + pos2.x) / 2.0, (pos1.y + pos2.y) / 2.0);
+                // Merge! Use combined velocity
                 let combined_vel = Vec2::new((vel1.x + vel2.x) / 2.0, (vel1.y + vel2.y) / 2.0);
                 let new_size = (avg_size * merge_config.growth_factor).min(merge_config.max_size);
                 let keep_shiny = shiny1 || shiny2;
 
-                to_merge.push((e1, e2, mid_pos, combined_vel, new_size, keep_shiny));
+                to_merge.push((e1, e2, combined_vel, new_size, keep_shiny));
                 already_merged.insert(e1);
                 already_merged.insert(e2);
                 break; // Only one merge per entity per frame
@@ -477,14 +614,11 @@ pub fn speaki_merge_system(
     }
 
     // Execute merges
-    for (e1, e2, mid_pos, combined_vel, new_size, _keep_shiny) in to_merge {
+    for (e1, e2, combined_vel, new_size, _keep_shiny) in to_merge {
         // Send merge event (for sound effects, etc.)
         merge_events.write(MergeSpeakiEvent {
             entity1: e1,
             entity2: e2,
-            position: mid_pos,
-            combined_velocity: combined_vel,
-            new_size,
         });
 
         // Despawn one entity
@@ -502,3 +636,116 @@ pub fn speaki_merge_system(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::ecs::system::RunSystemOnce;
+
+    /// World with one held and one free speaki, both moving 10 px per step
+    fn world(speed: f32, paused: bool) -> (World, Entity, Entity) {
+        let mut world = World::new();
+        world.insert_resource(Time::<()>::default());
+        world.insert_resource(PhysicsConfig::default());
+        let mut sim_speed = SimulationSpeed::default();
+        sim_speed.speed = speed;
+        sim_speed.paused = paused;
+        world.insert_resource(sim_speed);
+
+        let mut schedule = Schedule::new(SimulationStep);
+        schedule.add_systems((movement_system, rotation_system).chain());
+        world.add_schedule(schedule);
+
+        let mut spawn = |held: bool| {
+            let mut entity = world.spawn((
+                Speaki,
+                Transform::default(),
+                Velocity::new(10.0, 0.0),
+                RotationState { speed: 0.0 },
+            ));
+            if held {
+                entity.insert(Dragged);
+            }
+            entity.id()
+        };
+        let held = spawn(true);
+        let free = spawn(false);
+        (world, held, free)
+    }
+
+    /// Run one frame: held speakis, then the simulation steps
+    fn frame(world: &mut World) {
+        world.run_system_once(held_movement_system).unwrap();
+        simulation_step_system(world);
+    }
+
+    fn x(world: &World, entity: Entity) -> f32 {
+        world.get::<Transform>(entity).unwrap().translation.x
+    }
+
+    #[test]
+    fn held_speaki_moves_while_paused() {
+        let (mut world, held, free) = world(1.0, true);
+        frame(&mut world);
+
+        assert_eq!(x(&world, held), 10.0);
+        assert_eq!(x(&world, free), 0.0);
+    }
+
+    #[test]
+    fn held_speaki_moves_once_per_frame_at_any_speed() {
+        for (speed, free_x) in [(2.0, 20.0), (0.25, 2.5), (1.0, 10.0)] {
+            let (mut world, held, free) = world(speed, false);
+            frame(&mut world);
+
+            assert_eq!(x(&world, held), 10.0, "speed {speed}");
+            assert_eq!(x(&world, free), free_x, "speed {speed}");
+        }
+    }
+
+    #[test]
+    fn slow_motion_moves_every_frame() {
+        for speed in [0.5, 0.25] {
+            let (mut world, _, free) = world(speed, false);
+            for n in 1..=8 {
+                frame(&mut world);
+                assert_eq!(x(&world, free), 10.0 * speed * n as f32, "speed {speed}");
+            }
+        }
+    }
+
+    #[test]
+    fn every_dragged_speaki_is_kinematic() {
+        let mut world = World::new();
+        world.insert_resource(PhysicsConfig::default());
+        world.init_resource::<SimulationSpeed>();
+        world.init_resource::<Messages<SpeakiCollisionEvent>>();
+
+        // A dragged group of two, each overlapping a free speaki
+        let mut spawn = |x: f32, dragged: bool| {
+            let mut entity = world.spawn((
+                Speaki,
+                Transform::from_xyz(x, 0.0, 0.0),
+                Velocity::default(),
+                RotationState { speed: 0.0 },
+                SpeakiSize(100.0),
+            ));
+            if dragged {
+                entity.insert(Dragged);
+            }
+            entity.id()
+        };
+        let group = [spawn(0.0, true), spawn(500.0, true)];
+        let free = [spawn(90.0, false), spawn(590.0, false)];
+
+        world.run_system_once(speaki_collision_system).unwrap();
+
+        for entity in group {
+            let vel = world.get::<Velocity>(entity).unwrap();
+            assert_eq!((vel.x, vel.y), (0.0, 0.0));
+        }
+        for entity in free {
+            assert!(world.get::<Velocity>(entity).unwrap().x > 0.0);
+        }
+    }
+}
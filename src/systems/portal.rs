@@ -0,0 +1,483 @@
+use std::collections::{HashMap, HashSet};
+use std::net::{Ipv4Addr, SocketAddr, UdpSocket};
+
+use bevy::prelude::*;
+use bevy::window::WindowPosition;
+use serde::{Deserialize, Serialize};
+
+use crate::components::*;
+use crate::events::*;
+use crate::resources::*;
+use crate::systems::spawn_speaki_with;
+
+/// Instances pick the first free loopback port in this range
+const PORTAL_PORT_BASE: u16 = 47810;
+const PORTAL_PORT_COUNT: u16 = 8;
+
+/// How often each instance announces itself to the others
+const HELLO_INTERVAL: f32 = 0.5;
+/// Peers that have not said hello for this long are forgotten
+const PEER_TIMEOUT: f32 = 2.0;
+/// How far apart (in screen pixels) two windows may be to count as neighbours
+const NEIGHBOUR_GAP: i32 = 64;
+/// Smallest size accepted for an arriving speaki
+const MIN_SIZE: f32 = 50.0;
+
+/// Message exchanged between instances over loopback UDP
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum PortalMessage {
+    /// Heartbeat with the sender's window rect in screen pixels (if known)
+    Hello { rect: Option<[i32; 4]> },
+    /// A speaki crossing over
+    Transfer(TransferredSpeaki),
+}
+
+/// Everything needed to recreate a speaki on the other side
+#[derive(Serialize, Deserialize)]
+struct TransferredSpeaki {
+    edge: PortalEdge, // Edge it left through
+    offset: f32,      // Position along the edge (0.0 to 1.0)
+    size: f32,
+    velocity: [f32; 2],
+    rotation_speed: f32,
+    sprite_index: usize,
+    shiny_color: Option<[f32; 3]>,
+}
+
+struct PeerInfo {
+    rect: Option<IRect>,
+    last_seen: f32,
+}
+
+/// Local peer link to other running instances
+#[derive(Resource)]
+pub struct PortalLink {
+    socket: UdpSocket,
+    port: u16,
+    peers: HashMap<u16, PeerInfo>,
+    last_hello: f32,
+}
+
+impl TransferredSpeaki {
+    /// Make a received speaki safe to spawn: anyone on this machine can send
+    /// to the port, so reject non-finite numbers and clamp the rest
+    fn sanitized(mut self, max_size: f32, max_speed: f32, sprite_count: usize) -> Option<Self> {
+        let [vx, vy] = self.velocity;
+        let finite = [self.offset, self.size, vx, vy, self.rotation_speed]
+            .iter()
+            .all(|v| v.is_finite());
+        if !finite {
+            return None;
+        }
+
+        self.offset = self.offset.clamp(0.0, 1.0);
+        self.size = self.size.clamp(MIN_SIZE, max_size.max(MIN_SIZE));
+        self.velocity = Vec2::new(vx, vy).clamp_length_max(max_speed).into();
+        self.rotation_speed = self.rotation_speed.clamp(-1.0, 1.0);
+        if self.sprite_index >= sprite_count {
+            self.sprite_index = 0;
+        }
+        self.shiny_color = self
+            .shiny_color
+            .filter(|rgb| rgb.iter().all(|c| c.is_finite()))
+            .map(|rgb| rgb.map(|c| c.clamp(0.0, 1.0)));
+        Some(self)
+    }
+}
+
+impl PortalLink {
+    /// Pick the neighbouring instance on the given edge
+    fn neighbour(&self, edge: PortalEdge, own_rect: Option<IRect>) -> Option<u16> {
+        if let Some(own) = own_rect {
+            // Window positions known: choose the closest window on that side
+            let candidate = self
+                .peers
+                .iter()
+                .filter_map(|(&port, peer)| {
+                    let other = peer.rect?;
+                    let overlaps_y = other.min.y < own.max.y && other.max.y > own.min.y;
+                    let overlaps_x = other.min.x < own.max.x && other.max.x > own.min.x;
+                    let gap = match edge {
+                        PortalEdge::Right if overlaps_y => other.min.x - own.max.x,
+                        PortalEdge::Left if overlaps_y => own.min.x - other.max.x,
+                        PortalEdge::Bottom if overlaps_x => other.min.y - own.max.y,
+                        PortalEdge::Top if overlaps_x => own.min.y - other.max.y,
+                        _ => return None,
+                    };
+                    (gap >= -NEIGHBOUR_GAP).then_some((gap, port))
+                })
+                .min();
+
+            if let Some((_, port)) = candidate {
+                return Some(port);
+            }
+
+            // Only fall back to port order if some peer doesn't report its position
+            if self.peers.values().all(|p| p.rect.is_some()) {
+                return None;
+            }
+        }
+
+        // No window positions (e.g. Wayland): order instances by port
+        let mut ports: Vec<u16> = self.peers.keys().copied().collect();
+        ports.sort_unstable();
+        match edge {
+            PortalEdge::Right | PortalEdge::Bottom => ports
+                .iter()
+                .find(|&&p| p > self.port)
+                .or(ports.first())
+                .copied(),
+            PortalEdge::Left | PortalEdge::Top => ports
+                .iter()
+                .rev()
+                .find(|&&p| p < self.port)
+                .or(ports.last())
+                .copied(),
+        }
+    }
+
+    fn send(&self, port: u16, message: &PortalMessage) {
+        if let Ok(bytes) = serde_json::to_vec(message) {
+            let _ = self
+                .socket
+                .send_to(&bytes, SocketAddr::from((Ipv4Addr::LOCALHOST, port)));
+        }
+    }
+}
+
+/// Window rect in screen pixels, if the platform reports window positions
+fn window_rect(window: &Window) -> Option<IRect> {
+    match window.position {
+        WindowPosition::At(pos) => Some(IRect::from_corners(
+            pos,
+            pos + IVec2::new(
+                window.physical_width() as i32,
+                window.physical_height() as i32,
+            ),
+        )),
+        _ => None,
+    }
+}
+
+/// Bind the peer link socket on the first free loopback port
+pub fn setup_portal_link(mut commands: Commands) {
+    for port in PORTAL_PORT_BASE..PORTAL_PORT_BASE + PORTAL_PORT_COUNT {
+        let Ok(socket) = UdpSocket::bind((Ipv4Addr::LOCALHOST, port)) else {
+            continue;
+        };
+
+        if socket.set_nonblocking(true).is_err() {
+            continue;
+        }
+
+        info!("Portal link listening on port {}", port);
+        commands.insert_resource(PortalLink {
+            socket,
+            port,
+            peers: HashMap::new(),
+            last_hello: f32::NEG_INFINITY,
+        });
+        return;
+    }
+
+    warn!("Portal link disabled: no free port");
+}
+
+/// Announce this instance, track peers and spawn speakis arriving from them
+pub fn portal_link_system(
+    mut commands: Commands,
+    link: Option<ResMut<PortalLink>>,
+    (window, border): (Single<&Window>, Res<BorderConfig>),
+    (sprites, shiny_config): (Res<SpriteAssets>, Res<ShinyConfig>),
+    (merge_config, physics): (Res<MergeConfig>, Res<PhysicsConfig>),
+    time: Res<Time>,
+) {
+    let Some(mut link) = link else {
+        return;
+    };
+
+    let current_time = time.elapsed_secs();
+
+    // Heartbeat to every other port in the range
+    if current_time - link.last_hello > HELLO_INTERVAL {
+        link.last_hello = current_time;
+        let hello = PortalMessage::Hello {
+            rect: window_rect(&window).map(|r| [r.min.x, r.min.y, r.max.x, r.max.y]),
+        };
+        for port in PORTAL_PORT_BASE..PORTAL_PORT_BASE + PORTAL_PORT_COUNT {
+            if port != link.port {
+                link.send(port, &hello);
+            }
+        }
+    }
+
+    let bounds = border.bounds(window.width(), window.height());
+    let mut buf = [0u8; 2048];
+
+    while let Ok((len, from)) = link.socket.recv_from(&mut buf) {
+        let Ok(message) = serde_json::from_slice::<PortalMessage>(&buf[..len]) else {
+            continue;
+        };
+
+        match message {
+            PortalMessage::Hello { rect } => {
+                link.peers.insert(
+                    from.port(),
+                    PeerInfo {
+                        rect: rect.map(|[x0, y0, x1, y1]| IRect::new(x0, y0, x1, y1)),
+                        last_seen: current_time,
+                    },
+                );
+            }
+            PortalMessage::Transfer(speaki) => {
+                let Some(speaki) = speaki.sanitized(
+                    merge_config.max_size,
+                    physics.max_throw_speed,
+                    sprites.states.len(),
+                ) else {
+                    continue;
+                };
+
+                // Enter through the opposite edge, just inside the box
+                let half_size = speaki.size / 2.0;
+                let offset = speaki.offset;
+                let along_x = bounds.min.x + offset * bounds.width();
+                let along_y = bounds.min.y + offset * bounds.height();
+                let position = match speaki.edge.opposite() {
+                    PortalEdge::Left => Vec2::new(bounds.min.x + half_size, along_y),
+                    PortalEdge::Right => Vec2::new(bounds.max.x - half_size, along_y),
+                    PortalEdge::Top => Vec2::new(along_x, bounds.max.y - half_size),
+                    PortalEdge::Bottom => Vec2::new(along_x, bounds.min.y + half_size),
+                };
+
+                let entity = spawn_speaki_with(
+                    &mut commands,
+                    position,
+                    Vec2::from(speaki.velocity),
+                    speaki.size,
+                    speaki.shiny_color.map(|[r, g, b]| Color::srgb(r, g, b)),
+                    &sprites,
+                    &shiny_config,
+                );
+                commands.entity(entity).insert((
+                    RotationState {
+                        speed: speaki.rotation_speed,
+                    },
+                    SpriteState {
+                        current_index: speaki.sprite_index,
+                    },
+                ));
+            }
+        }
+    }
+
+    link.peers.retain(|_, peer| current_time - peer.last_seen < PEER_TIMEOUT);
+}
+
+/// Speaki state sent through a portal, or bounced back when nobody is on the other side
+type LeavingSpeaki = (
+    &'static mut Transform,
+    &'static mut Velocity,
+    &'static RotationState,
+    &'static SpeakiSize,
+    &'static SpriteState,
+    Option<&'static Shiny>,
+);
+
+/// Send speakis leaving through a portal edge to the neighbouring instance
+pub fn portal_exit_system(
+    mut commands: Commands,
+    mut exit_events: MessageReader<PortalExitEvent>,
+    link: Option<Res<PortalLink>>,
+    window: Single<&Window>,
+    border: Res<BorderConfig>,
+    mut query: Query<LeavingSpeaki, With<Speaki>>,
+) {
+    let bounds = border.bounds(window.width(), window.height());
+    let own_rect = window_rect(&window);
+
+    // Every simulation sub-step (and each edge at a corner) reports the exit
+    // again until the despawn is applied, so only handle a speaki once
+    let mut handled = HashSet::new();
+
+    for event in exit_events.read() {
+        if !handled.insert(event.entity) {
+            continue;
+        }
+
+        let Ok((mut transform, mut vel, rot, size, sprite_state, shiny)) =
+            query.get_mut(event.entity)
+        else {
+            continue;
+        };
+
+        let half_size = size.0 / 2.0;
+
+        let target = link
+            .as_ref()
+            .and_then(|link| link.neighbour(event.edge, own_rect).map(|port| (link, port)));
+
+        if let Some((link, port)) = target {
+            let offset = match event.edge {
+                PortalEdge::Left | PortalEdge::Right => {
+                    (transform.translation.y - bounds.min.y) / bounds.height()
+                }
+                PortalEdge::Top | PortalEdge::Bottom => {
+                    (transform.translation.x - bounds.min.x) / bounds.width()
+                }
+            };
+            let shiny_color = shiny.map(|s| {
+                let c = s.base_color.to_srgba();
+                [c.red, c.green, c.blue]
+            });
+
+            link.send(
+                port,
+                &PortalMessage::Transfer(TransferredSpeaki {
+                    edge: event.edge,
+                    offset,
+                    size: size.0,
+                    velocity: [vel.x, vel.y],
+                    rotation_speed: rot.speed,
+                    sprite_index: sprite_state.current_index,
+                    shiny_color,
+                }),
+            );
+
+            commands.entity(event.entity).despawn();
+            continue;
+        }
+
+        // Nobody on the other side: bounce back in like a normal wall
+        match event.edge {
+            PortalEdge::Left => {
+                transform.translation.x = bounds.min.x + half_size;
+                vel.x = vel.x.abs();
+            }
+            PortalEdge::Right => {
+                transform.translation.x = bounds.max.x - half_size;
+                vel.x = -vel.x.abs();
+            }
+            PortalEdge::Top => {
+                transform.translation.y = bounds.max.y - half_size;
+                vel.y = -vel.y.abs();
+            }
+            PortalEdge::Bottom => {
+                transform.translation.y = bounds.min.y + half_size;
+                vel.y = vel.y.abs();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn link(port: u16, peers: &[(u16, Option<IRect>)]) -> PortalLink {
+        PortalLink {
+            socket: UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).unwrap(),
+            port,
+            peers: peers
+                .iter()
+                .map(|&(port, rect)| {
+                    let peer = PeerInfo {
+                        rect,
+                        last_seen: 0.0,
+                    };
+                    (port, peer)
+                })
+                .collect(),
+            last_hello: 0.0,
+        }
+    }
+
+    fn transfer() -> TransferredSpeaki {
+        TransferredSpeaki {
+            edge: PortalEdge::Right,
+            offset: 0.5,
+            size: 150.0,
+            velocity: [10.0, 0.0],
+            rotation_speed: 0.1,
+            sprite_index: 0,
+            shiny_color: None,
+        }
+    }
+
+    #[test]
+    fn neighbour_is_closest_window_on_that_side() {
+        let own = IRect::new(0, 0, 800, 600);
+        let link = link(
+            47810,
+            &[
+                (47811, Some(IRect::new(1700, 0, 2500, 600))),
+                (47812, Some(IRect::new(810, 100, 1610, 700))),
+                (47813, Some(IRect::new(-820, 0, -20, 600))),
+            ],
+        );
+
+        assert_eq!(link.neighbour(PortalEdge::Right, Some(own)), Some(47812));
+        assert_eq!(link.neighbour(PortalEdge::Left, Some(own)), Some(47813));
+    }
+
+    #[test]
+    fn no_neighbour_when_every_window_is_elsewhere() {
+        let own = IRect::new(0, 0, 800, 600);
+        let link = link(47810, &[(47811, Some(IRect::new(0, 700, 800, 1300)))]);
+
+        assert_eq!(link.neighbour(PortalEdge::Right, Some(own)), None);
+        assert_eq!(link.neighbour(PortalEdge::Bottom, Some(own)), Some(47811));
+    }
+
+    #[test]
+    fn neighbour_falls_back_to_port_order() {
+        let link = link(47811, &[(47810, None), (47812, None)]);
+        assert_eq!(link.neighbour(PortalEdge::Right, None), Some(47812));
+        assert_eq!(link.neighbour(PortalEdge::Left, None), Some(47810));
+
+        // Wraps around at the ends
+        let link = self::link(47812, &[(47810, None), (47811, None)]);
+        assert_eq!(link.neighbour(PortalEdge::Bottom, None), Some(47810));
+        assert_eq!(link.neighbour(PortalEdge::Top, None), Some(47811));
+    }
+
+    #[test]
+    fn neighbour_uses_port_order_if_a_peer_has_no_position() {
+        let own = IRect::new(0, 0, 800, 600);
+        let link = link(47810, &[(47811, None)]);
+        assert_eq!(link.neighbour(PortalEdge::Right, Some(own)), Some(47811));
+    }
+
+    #[test]
+    fn transfer_rejects_non_finite_values() {
+        let mut speaki = transfer();
+        speaki.velocity = [f32::NAN, 0.0];
+        assert!(speaki.sanitized(600.0, 80.0, 20).is_none());
+
+        let mut speaki = transfer();
+        speaki.size = f32::INFINITY;
+        assert!(speaki.sanitized(600.0, 80.0, 20).is_none());
+    }
+
+    #[test]
+    fn transfer_is_clamped() {
+        let mut speaki = transfer();
+        speaki.size = 0.0;
+        speaki.offset = 3.0;
+        speaki.velocity = [1e9, 0.0];
+        speaki.sprite_index = 99;
+        speaki.shiny_color = Some([2.0, 0.5, -1.0]);
+
+        let speaki = speaki.sanitized(600.0, 80.0, 20).unwrap();
+        assert_eq!(speaki.size, MIN_SIZE);
+        assert_eq!(speaki.offset, 1.0);
+        assert_eq!(speaki.velocity, [80.0, 0.0]);
+        assert_eq!(speaki.sprite_index, 0);
+        assert_eq!(speaki.shiny_color, Some([1.0, 0.5, 0.0]));
+
+        let mut speaki = transfer();
+        speaki.size = 1e6;
+        assert_eq!(speaki.sanitized(600.0, 80.0, 20).unwrap().size, 600.0);
+    }
+}
@@ -0,0 +1,132 @@
+use bevy::prelude::*;
+
+use crate::components::*;
+use crate::events::*;
+use crate::resources::*;
+use crate::systems::Voices;
+
+/// Size change per key press when resizing a selection
+const RESIZE_STEP: f32 = 1.1;
+/// Smallest size a selection can be shrunk to
+const MIN_SIZE: f32 = 50.0;
+
+/// Track the rubber band and select the speakis inside it on release
+pub fn selection_box_system(
+    mut commands: Commands,
+    mouse_button: Res<ButtonInput<MouseButton>>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    window: Single<&Window>,
+    camera_q: Query<(&Camera, &GlobalTransform)>,
+    speaki_query: Query<(Entity, &Transform, Has<Selected>), With<Speaki>>,
+    mut selection_box: ResMut<SelectionBox>,
+) {
+    let Some(start) = selection_box.start else {
+        return;
+    };
+
+    if let Ok((camera, camera_transform)) = camera_q.single()
+        && let Some(cursor_pos) = window
+            .cursor_position()
+            .and_then(|p| camera.viewport_to_world_2d(camera_transform, p).ok())
+    {
+        selection_box.end = cursor_pos;
+    }
+
+    if !mouse_button.just_released(MouseButton::Left) {
+        return;
+    }
+
+    // Ctrl adds to the selection, otherwise the box replaces it
+    let add = keyboard.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+    let rect = Rect::from_corners(start, selection_box.end);
+
+    for (entity, transform, selected) in speaki_query.iter() {
+        let inside = rect.contains(transform.translation.truncate());
+        if inside && !selected {
+            commands.entity(entity).insert(Selected);
+        } else if !inside && selected && !add {
+            commands.entity(entity).remove::<Selected>();
+        }
+    }
+
+    selection_box.start = None;
+}
+
+/// Keyboard shortcuts for the selection: delete, resize, select all, clear
+pub fn selection_keyboard_system(
+    mut commands: Commands,
+    (keyboard, key_bindings): (Res<ButtonInput<KeyCode>>, Res<KeyBindings>),
+    speaki_query: Query<Entity, With<Speaki>>,
+    mut selected_query: Query<(Entity, &mut SpeakiSize, &mut Sprite), With<Selected>>,
+    merge_config: Res<MergeConfig>,
+    mut despawn_events: MessageWriter<DespawnSpeakiEvent>,
+    mut voices: Voices,
+) {
+    if key_bindings.just_pressed(KeyAction::SelectAll, &keyboard) {
+        for entity in speaki_query.iter() {
+            commands.entity(entity).insert(Selected);
+        }
+        return;
+    }
+
+    if key_bindings.just_pressed(KeyAction::ClearSelection, &keyboard) {
+        for (entity, ..) in selected_query.iter() {
+            commands.entity(entity).remove::<Selected>();
+        }
+        return;
+    }
+
+    // Remove every selected speaki
+    if key_bindings.just_pressed(KeyAction::DeleteSelected, &keyboard) {
+        let mut removed = None;
+        for (entity, ..) in selected_query.iter() {
+            despawn_events.write(DespawnSpeakiEvent { entity });
+            removed = Some(entity);
+        }
+
+        // One voice for the whole group
+        if let Some(entity) = removed
+            && let Some(idx) = voices.groups.pick(VoiceCategory::Remove)
+        {
+            voices.events.write(PlayVoiceEvent {
+                entity: Some(entity),
+                voice_index: idx,
+                volume: voices.config.remove_volume,
+                kind: VoiceKind::Remove,
+            });
+        }
+        return;
+    }
+
+    // Resize every selected speaki
+    let factor = if key_bindings.just_pressed(KeyAction::GrowSelected, &keyboard) {
+        RESIZE_STEP
+    } else if key_bindings.just_pressed(KeyAction::ShrinkSelected, &keyboard) {
+        1.0 / RESIZE_STEP
+    } else {
+        return;
+    };
+
+    for (_, mut size, mut sprite) in selected_query.iter_mut() {
+        size.0 = (size.0 * factor).clamp(MIN_SIZE, merge_config.max_size);
+        sprite.custom_size = Some(Vec2::splat(size.0));
+    }
+}
+
+/// Outline selected speakis and draw the rubber band
+pub fn selection_gizmo_system(
+    mut gizmos: Gizmos,
+    selection_box: Res<SelectionBox>,
+    selected_query: Query<(&Transform, &SpeakiSize), With<Selected>>,
+) {
+    let color = Color::srgb(0.4, 0.8, 1.0);
+
+    for (transform, size) in selected_query.iter() {
+        gizmos.circle_2d(transform.translation.truncate(), size.0 / 2.0 + 4.0, color);
+    }
+
+    if let Some(start) = selection_box.start {
+        let rect = Rect::from_corners(start, selection_box.end);
+        gizmos.rect_2d(rect.center(), rect.size(), color);
+    }
+}
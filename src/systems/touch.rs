@@ -0,0 +1,348 @@
+use std::collections::HashMap;
+
+use bevy::ecs::system::SystemParam;
+use bevy::picking::pointer::PointerId;
+use bevy::prelude::*;
+
+use crate::components::*;
+use crate::events::*;
+use crate::resources::*;
+use crate::systems::{THROW_SPIN_THRESHOLD, Voices, grab_offset, spawn_speaki, spring_drag};
+
+/// Holding a speaki still for this long removes it
+const LONG_PRESS_TIME: f32 = 0.6;
+/// Finger movement (pixels) that cancels a long press
+const LONG_PRESS_SLOP: f32 = 12.0;
+/// Smallest size a pinch can shrink a speaki to
+const PINCH_MIN_SIZE: f32 = 50.0;
+
+/// What a new touch can do: grab the speaki under it or spawn one there
+#[derive(SystemParam)]
+pub struct TouchActions<'w, 's> {
+    commands: Commands<'w, 's>,
+    z_order: ResMut<'w, ZOrder>,
+    config: Res<'w, GameConfig>,
+    sprites: Res<'w, SpriteAssets>,
+    shiny_config: Res<'w, ShinyConfig>,
+    voices: Voices<'w>,
+}
+
+/// Start touch drags, pinches and spawns
+pub fn touch_press_system(
+    mut press_events: MessageReader<Pointer<Press>>,
+    touches: Res<Touches>,
+    camera_q: Query<(&Camera, &GlobalTransform)>,
+    mut speaki_query: Query<(&mut Transform, &SpeakiSize, Has<Dragged>), With<Speaki>>,
+    mut touch_state: ResMut<TouchState>,
+    actions: TouchActions,
+    time: Res<Time>,
+) {
+    let TouchActions {
+        mut commands,
+        mut z_order,
+        config,
+        sprites,
+        shiny_config,
+        mut voices,
+    } = actions;
+
+    let Ok((camera, camera_transform)) = camera_q.single() else {
+        return;
+    };
+
+    // Target per new touch, preferring a speaki over the window behind it
+    let mut presses: HashMap<u64, (Entity, Vec2)> = HashMap::new();
+    for event in press_events.read() {
+        let PointerId::Touch(id) = event.pointer_id else {
+            continue;
+        };
+        let Ok(pos) =
+            camera.viewport_to_world_2d(camera_transform, event.pointer_location.position)
+        else {
+            continue;
+        };
+
+        let is_speaki = speaki_query.contains(event.entity);
+        let replace = presses
+            .get(&id)
+            .is_none_or(|(e, _)| is_speaki || !speaki_query.contains(*e));
+        if replace {
+            presses.insert(id, (event.entity, pos));
+        }
+    }
+
+    let current_time = time.elapsed_secs();
+
+    for (id, (target, pos)) in presses {
+        let mut drag = TouchDrag {
+            entity: None,
+            grab_offset: Vec2::ZERO,
+            spawned: false,
+            press_pos: pos,
+            press_time: current_time,
+            moved: false,
+            history: DragHistory::default(),
+        };
+        drag.history.start(current_time, pos);
+
+        // Second finger on the held speaki (or beside it) starts a pinch
+        if touch_state.pinch.is_none() {
+            let holders: Vec<(u64, Entity)> = touch_state
+                .drags
+                .iter()
+                .filter_map(|(&other, d)| d.entity.map(|e| (other, e)))
+                .collect();
+
+            if let [(other, held)] = holders[..]
+                && (target == held || !speaki_query.contains(target))
+                && let Ok((_, size, _)) = speaki_query.get(held)
+                && let Some(other_pos) = touches.get_pressed(other).and_then(|t| {
+                    camera
+                        .viewport_to_world_2d(camera_transform, t.position())
+                        .ok()
+                })
+            {
+                touch_state.pinch = Some(Pinch {
+                    entity: held,
+                    touches: (other, id),
+                    start_distance: pos.distance(other_pos).max(1.0),
+                    start_size: size.0,
+                });
+                touch_state.drags.insert(id, drag);
+                continue;
+            }
+        }
+
+        if let Ok((mut transform, _, held)) = speaki_query.get_mut(target) {
+            // Already held by another finger, the mouse or a remote user
+            if held {
+                continue;
+            }
+
+            transform.translation.z = z_order.next();
+            commands.entity(target).insert(Dragged);
+            drag.entity = Some(target);
+            drag.grab_offset = grab_offset(&transform, pos);
+
+            if let Some(idx) = voices.groups.pick(VoiceCategory::Drag) {
+                voices.events.write(PlayVoiceEvent {
+                    entity: Some(target),
+                    voice_index: idx,
+                    volume: voices.config.grab_volume,
+                    kind: VoiceKind::Grab,
+                });
+            }
+        } else if config.click_to_add {
+            // Tap on empty space: create a speaki held by this finger
+            let entity = spawn_speaki(
+                &mut commands,
+                pos,
+                Vec2::ZERO,
+                config.speaki_size,
+                &sprites,
+                &shiny_config,
+            );
+            commands.entity(entity).insert(Dragged);
+            drag.entity = Some(entity);
+            drag.spawned = true;
+
+            if let Some(idx) = voices.groups.pick(VoiceCategory::Create) {
+                voices.events.write(PlayVoiceEvent {
+                    entity: Some(entity),
+                    voice_index: idx,
+                    volume: voices.config.create_volume,
+                    kind: VoiceKind::Create,
+                });
+            }
+        }
+
+        touch_state.drags.insert(id, drag);
+    }
+}
+
+/// Move held speakis with their fingers, resize on pinch and remove on long press
+pub fn touch_drag_system(
+    (touches, camera_q): (Res<Touches>, Query<(&Camera, &GlobalTransform)>),
+    mut speaki_query: Query<
+        (
+            &mut Transform,
+            &mut Velocity,
+            &mut RotationState,
+            &mut SpeakiSize,
+            &mut Sprite,
+        ),
+        With<Speaki>,
+    >,
+    mut touch_state: ResMut<TouchState>,
+    (merge_config, physics): (Res<MergeConfig>, Res<PhysicsConfig>),
+    time: Res<Time>,
+    mut despawn_events: MessageWriter<DespawnSpeakiEvent>,
+    mut voices: Voices,
+) {
+    let Ok((camera, camera_transform)) = camera_q.single() else {
+        return;
+    };
+
+    let current_time = time.elapsed_secs();
+    let touch_state = &mut *touch_state;
+    let pinched = touch_state.pinch.as_ref().map(|p| p.entity);
+
+    for (&id, drag) in touch_state.drags.iter_mut() {
+        let Some(pos) = touches.get_pressed(id).and_then(|t| {
+            camera
+                .viewport_to_world_2d(camera_transform, t.position())
+                .ok()
+        }) else {
+            continue;
+        };
+
+        if pos.distance(drag.press_pos) > LONG_PRESS_SLOP {
+            drag.moved = true;
+        }
+
+        drag.history.push(current_time, pos);
+
+        let Some(entity) = drag.entity else {
+            continue;
+        };
+
+        // Long press on a grabbed speaki removes it
+        if !drag.moved
+            && !drag.spawned
+            && speaki_query.contains(entity)
+            && pinched != Some(entity)
+            && current_time - drag.press_time > LONG_PRESS_TIME
+        {
+            despawn_events.write(DespawnSpeakiEvent { entity });
+            drag.entity = None;
+
+            if let Some(idx) = voices.groups.pick(VoiceCategory::Remove) {
+                voices.events.write(PlayVoiceEvent {
+                    entity: Some(entity),
+                    voice_index: idx,
+                    volume: voices.config.remove_volume,
+                    kind: VoiceKind::Remove,
+                });
+            }
+            continue;
+        }
+
+        // Pinched speakis follow both fingers below
+        if pinched == Some(entity) {
+            continue;
+        }
+
+        if let Ok((mut transform, mut vel, mut rot, size, _)) = speaki_query.get_mut(entity) {
+            if physics.spring_drag {
+                spring_drag(
+                    &transform,
+                    &mut vel,
+                    &mut rot,
+                    size.0,
+                    drag.grab_offset,
+                    pos,
+                    &physics,
+                );
+            } else {
+                transform.translation.x = pos.x;
+                transform.translation.y = pos.y;
+                vel.x = 0.0;
+                vel.y = 0.0;
+            }
+        }
+    }
+
+    // Pinch: center between the fingers, size scaled by their distance
+    if let Some(pinch) = &touch_state.pinch {
+        let positions = [pinch.touches.0, pinch.touches.1].map(|id| {
+            touches.get_pressed(id).and_then(|t| {
+                camera
+                    .viewport_to_world_2d(camera_transform, t.position())
+                    .ok()
+            })
+        });
+
+        if let [Some(a), Some(b)] = positions
+            && let Ok((mut transform, mut vel, _, mut size, mut sprite)) =
+                speaki_query.get_mut(pinch.entity)
+        {
+            let center = (a + b) / 2.0;
+            transform.translation.x = center.x;
+            transform.translation.y = center.y;
+            vel.x = 0.0;
+            vel.y = 0.0;
+
+            let scale = a.distance(b) / pinch.start_distance;
+            let new_size = (pinch.start_size * scale).clamp(PINCH_MIN_SIZE, merge_config.max_size);
+            if (new_size - size.0).abs() > 0.5 {
+                size.0 = new_size;
+                sprite.custom_size = Some(Vec2::splat(new_size));
+            }
+        }
+    }
+}
+
+/// Throw speakis when their finger lifts, with that finger's own velocity
+pub fn touch_release_system(
+    mut commands: Commands,
+    touches: Res<Touches>,
+    camera_q: Query<(&Camera, &GlobalTransform)>,
+    mut query: Query<(&mut Velocity, &mut RotationState, &mut IdleVoiceTimer), With<Dragged>>,
+    mut touch_state: ResMut<TouchState>,
+    physics: Res<PhysicsConfig>,
+    time: Res<Time>,
+) {
+    let Ok((camera, camera_transform)) = camera_q.single() else {
+        return;
+    };
+
+    let current_time = time.elapsed_secs();
+
+    for touch in touches
+        .iter_just_released()
+        .chain(touches.iter_just_canceled())
+    {
+        let Some(mut drag) = touch_state.drags.remove(&touch.id()) else {
+            continue;
+        };
+
+        // Lifting one pinch finger hands the speaki to the other one
+        if let Some(pinch) = touch_state
+            .pinch
+            .take_if(|p| p.touches.0 == touch.id() || p.touches.1 == touch.id())
+        {
+            let other = if pinch.touches.0 == touch.id() {
+                pinch.touches.1
+            } else {
+                pinch.touches.0
+            };
+            if let Some(other_drag) = touch_state.drags.get_mut(&other) {
+                other_drag.entity = Some(pinch.entity);
+                other_drag.grab_offset = Vec2::ZERO;
+                other_drag.moved = true;
+            }
+            continue;
+        }
+
+        let Some(entity) = drag.entity else {
+            continue;
+        };
+
+        // Calculate throw velocity and spin from this finger's path; without
+        // a world position for the lift point, the path so far still counts
+        if let Ok(pos) = camera.viewport_to_world_2d(camera_transform, touch.position()) {
+            drag.history.push(current_time, pos);
+        }
+        let (throw_vel, spin) = physics.throw(&drag.history, current_time);
+
+        if let Ok((mut vel, mut rot, mut idle_timer)) = query.get_mut(entity) {
+            vel.x = throw_vel.x;
+            vel.y = throw_vel.y;
+            if spin.abs() > THROW_SPIN_THRESHOLD {
+                rot.speed = spin;
+            }
+            idle_timer.last_idle_time = current_time;
+            commands.entity(entity).remove::<Dragged>();
+        }
+    }
+}
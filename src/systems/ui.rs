@@ -1,8 +1,15 @@
+use bevy::ecs::system::SystemParam;
+use bevy::picking::hover::Hovered;
 use bevy::prelude::*;
+use bevy::window::{CursorIcon, PrimaryWindow, SystemCursorIcon};
 use bevy_egui::EguiContexts;
 use bevy_egui::egui;
+use bevy_kira_audio::prelude::*;
 
+use crate::components::*;
+use crate::events::*;
 use crate::resources::*;
+use crate::systems::{Voices, spawn_speaki_with};
 
 #[derive(Resource, Default)]
 pub struct SettingsOpen(pub bool);
@@ -50,37 +57,23 @@ pub fn setup_fonts_system(
     Ok(())
 }
 
-/// Toggle settings with Q key
-pub fn toggle_settings_system(
-    keyboard: Res<ButtonInput<KeyCode>>,
-    mut settings_open: ResMut<SettingsOpen>,
-) {
-    if keyboard.just_pressed(KeyCode::KeyQ) {
-        settings_open.0 = !settings_open.0;
-    }
-}
-
-/// Toggle title bar with Alt + T
-pub fn toggle_titlebar_system(
-    keyboard: Res<ButtonInput<KeyCode>>,
-    mut game_config: ResMut<GameConfig>,
-) {
-    if keyboard.pressed(KeyCode::AltLeft) && keyboard.just_pressed(KeyCode::KeyT) {
-        game_config.window_decorations = !game_config.window_decorations;
-    }
-}
-
 /// Render settings UI
 pub fn settings_ui_system(
     mut contexts: EguiContexts,
     settings_open: Res<SettingsOpen>,
-    mut audio_config: ResMut<AudioConfig>,
-    mut physics_config: ResMut<PhysicsConfig>,
-    mut game_config: ResMut<GameConfig>,
-    mut border_config: ResMut<BorderConfig>,
-    mut window_tracker: ResMut<WindowPositionTracker>,
-    mut shiny_config: ResMut<ShinyConfig>,
-    mut merge_config: ResMut<MergeConfig>,
+    (mut audio_config, mut music_player, mut voice_editor): (
+        ResMut<AudioConfig>,
+        ResMut<MusicPlayer>,
+        ResMut<VoiceEditor>,
+    ),
+    (mut physics_config, mut sim_speed): (ResMut<PhysicsConfig>, ResMut<SimulationSpeed>),
+    (mut game_config, mut shiny_config, mut merge_config): (
+        ResMut<GameConfig>,
+        ResMut<ShinyConfig>,
+        ResMut<MergeConfig>,
+    ),
+    (mut border_config, mut window_tracker): (ResMut<BorderConfig>, ResMut<WindowPositionTracker>),
+    (mut gamepad_config, mut key_bindings): (ResMut<GamepadConfig>, ResMut<KeyBindings>),
 ) -> Result {
     let ctx = contexts.ctx_mut()?;
 
@@ -105,6 +98,77 @@ pub fn settings_ui_system(
                                 egui::Slider::new(&mut audio_config.master_volume, 0.0..=1.0)
                                     .text("Master"),
                             );
+                            ui.checkbox(&mut audio_config.muted, "Mute");
+                            ui.horizontal(|ui| {
+                                ui.add(
+                                    egui::Slider::new(&mut audio_config.voice_volume, 0.0..=1.0)
+                                        .text("Voices"),
+                                );
+                                ui.checkbox(&mut audio_config.voice_muted, "Mute");
+                            });
+                            ui.horizontal(|ui| {
+                                ui.add(
+                                    egui::Slider::new(&mut audio_config.effects_volume, 0.0..=1.0)
+                                        .text("Effects"),
+                                );
+                                ui.checkbox(&mut audio_config.effects_muted, "Mute");
+                            });
+                            ui.horizontal(|ui| {
+                                ui.add(
+                                    egui::Slider::new(&mut audio_config.music_volume, 0.0..=1.0)
+                                        .text("Music"),
+                                );
+                                ui.checkbox(&mut audio_config.music_muted, "Mute");
+                            });
+                            ui.checkbox(&mut audio_config.music_enabled, "Play Music");
+                            ui.add_enabled_ui(audio_config.music_enabled, |ui| {
+                                if music_player.tracks.is_empty() {
+                                    ui.label("No tracks: add mp3/ogg files to the music folder");
+                                    return;
+                                }
+
+                                let current = music_player.current;
+                                let current_name = current
+                                    .and_then(|i| music_player.tracks.get(i))
+                                    .map_or("-", |track| track.name.as_str());
+                                let mut picked = None;
+                                egui::ComboBox::from_label("Track")
+                                    .selected_text(current_name)
+                                    .show_ui(ui, |ui| {
+                                        for (i, track) in music_player.tracks.iter().enumerate() {
+                                            if ui
+                                                .selectable_label(current == Some(i), &track.name)
+                                                .clicked()
+                                            {
+                                                picked = Some(i);
+                                            }
+                                        }
+                                    });
+                                ui.horizontal(|ui| {
+                                    if ui.button("Previous").clicked() {
+                                        picked = Some(music_player.previous_index());
+                                    }
+                                    if ui.button("Next").clicked() {
+                                        picked = Some(music_player.next_index());
+                                    }
+                                });
+                                if picked.is_some() {
+                                    music_player.requested = picked;
+                                }
+
+                                ui.add(
+                                    egui::Slider::new(
+                                        &mut audio_config.music_crossfade,
+                                        0.0..=10.0,
+                                    )
+                                    .text("Crossfade"),
+                                );
+                                ui.checkbox(
+                                    &mut audio_config.music_focus_pause,
+                                    "Pause When Unfocused",
+                                );
+                            });
+                            ui.separator();
                             ui.add(
                                 egui::Slider::new(&mut audio_config.grab_volume, 0.0..=1.0)
                                     .text("Grab"),
@@ -113,6 +177,10 @@ pub fn settings_ui_system(
                                 egui::Slider::new(&mut audio_config.bounce_volume, 0.0..=1.0)
                                     .text("Bounce"),
                             );
+                            ui.add(
+                                egui::Slider::new(&mut audio_config.collision_volume, 0.0..=1.0)
+                                    .text("Collision"),
+                            );
                             ui.add(
                                 egui::Slider::new(&mut audio_config.create_volume, 0.0..=1.0)
                                     .text("Create"),
@@ -129,6 +197,98 @@ pub fn settings_ui_system(
                                 egui::Slider::new(&mut audio_config.idle_frequency, 0.0..=1.0)
                                     .text("Idle Freq"),
                             );
+                            ui.checkbox(&mut audio_config.lip_sync, "Lip Sync");
+                            ui.add_enabled(
+                                audio_config.lip_sync,
+                                egui::Slider::new(&mut audio_config.lip_sync_threshold, 0.05..=0.9)
+                                    .text("Mouth Threshold"),
+                            );
+                            ui.checkbox(&mut audio_config.captions, "Captions");
+                            ui.add_enabled_ui(audio_config.captions, |ui| {
+                                egui::ComboBox::from_label("Caption Language")
+                                    .selected_text(audio_config.caption_language.label())
+                                    .show_ui(ui, |ui| {
+                                        for language in CaptionLanguage::ALL {
+                                            ui.selectable_value(
+                                                &mut audio_config.caption_language,
+                                                language,
+                                                language.label(),
+                                            );
+                                        }
+                                    });
+                            });
+                            ui.checkbox(&mut audio_config.ducking, "Duck Idle Chatter");
+                            ui.add_enabled(
+                                audio_config.ducking,
+                                egui::Slider::new(&mut audio_config.duck_level, 0.0..=1.0)
+                                    .text("Duck Level"),
+                            );
+                            ui.separator();
+                            ui.checkbox(&mut audio_config.impact_volume, "Scale by Impact");
+                            ui.add(
+                                egui::Slider::new(
+                                    &mut audio_config.hard_impact_speed,
+                                    10.0..=100.0,
+                                )
+                                .text("Hard Impact"),
+                            );
+                            ui.add(
+                                egui::Slider::new(
+                                    &mut audio_config.throw_voice_speed,
+                                    10.0..=100.0,
+                                )
+                                .text("Throw Voice Speed"),
+                            );
+                            ui.add(
+                                egui::Slider::new(&mut audio_config.wake_up_time, 5.0..=120.0)
+                                    .text("Wake Up After"),
+                            );
+                            ui.separator();
+                            ui.checkbox(&mut audio_config.stereo_panning, "Stereo Panning");
+                            ui.add_enabled(
+                                audio_config.stereo_panning,
+                                egui::Slider::new(&mut audio_config.pan_width, 0.0..=1.0)
+                                    .text("Pan Width"),
+                            );
+                            ui.checkbox(&mut audio_config.cursor_attenuation, "Cursor Distance");
+                            ui.add_enabled(
+                                audio_config.cursor_attenuation,
+                                egui::Slider::new(
+                                    &mut audio_config.attenuation_distance,
+                                    200.0..=3000.0,
+                                )
+                                .text("Fade Distance"),
+                            );
+                            ui.checkbox(&mut audio_config.size_pitch, "Pitch by Size");
+                            ui.add_enabled_ui(audio_config.size_pitch, |ui| {
+                                ui.add(
+                                    egui::Slider::new(&mut audio_config.min_pitch, 0.25..=1.0)
+                                        .text("Big Pitch"),
+                                );
+                                ui.add(
+                                    egui::Slider::new(&mut audio_config.max_pitch, 1.0..=2.5)
+                                        .text("Small Pitch"),
+                                );
+                            });
+                            ui.add(
+                                egui::Slider::new(&mut audio_config.pitch_variation, 0.0..=0.2)
+                                    .text("Pitch Jitter"),
+                            );
+                            ui.add(
+                                egui::Slider::new(&mut audio_config.max_voices, 1..=32)
+                                    .text("Max Voices"),
+                            );
+                            ui.add(
+                                egui::Slider::new(&mut audio_config.voice_cooldown, 0.0..=2.0)
+                                    .text("Voice Cooldown"),
+                            );
+                            ui.add(
+                                egui::Slider::new(&mut audio_config.group_cooldown, 0.0..=1.0)
+                                    .text("Group Cooldown"),
+                            );
+                            if ui.button("Edit Voice Mapping").clicked() {
+                                voice_editor.open = true;
+                            }
                         });
 
                     egui::CollapsingHeader::new("Physics")
@@ -150,6 +310,21 @@ pub fn settings_ui_system(
                                 egui::Slider::new(&mut physics_config.rotation_speed, 0.0..=1.0)
                                     .text("Rotation"),
                             );
+                            ui.horizontal(|ui| {
+                                ui.checkbox(&mut sim_speed.paused, "Paused");
+                                if ui.button("Step").clicked() {
+                                    sim_speed.step();
+                                }
+                            });
+                            ui.horizontal(|ui| {
+                                for speed in SimulationSpeed::SPEEDS {
+                                    ui.selectable_value(
+                                        &mut sim_speed.speed,
+                                        speed,
+                                        format!("{}x", speed),
+                                    );
+                                }
+                            });
                             ui.checkbox(&mut physics_config.collision_enabled, "Collision");
                             ui.add(
                                 egui::Slider::new(&mut physics_config.collision_damping, 0.0..=1.0)
@@ -166,6 +341,28 @@ pub fn settings_ui_system(
                                 )
                                 .text("Throw"),
                             );
+                            ui.add(
+                                egui::Slider::new(
+                                    &mut physics_config.max_throw_speed,
+                                    10.0..=200.0,
+                                )
+                                .text("Max Throw"),
+                            );
+                            ui.add(
+                                egui::Slider::new(&mut physics_config.throw_spin, 0.0..=2.0)
+                                    .text("Throw Spin"),
+                            );
+                            ui.checkbox(&mut physics_config.spring_drag, "Spring Drag");
+                            ui.add_enabled(
+                                physics_config.spring_drag,
+                                egui::Slider::new(&mut physics_config.drag_stiffness, 0.01..=1.0)
+                                    .text("Stiffness"),
+                            );
+                            ui.add_enabled(
+                                physics_config.spring_drag,
+                                egui::Slider::new(&mut physics_config.drag_damping, 0.0..=1.0)
+                                    .text("Drag Damp"),
+                            );
                             ui.add(
                                 egui::Slider::new(
                                     &mut physics_config.bounce_responsiveness,
@@ -190,6 +387,9 @@ pub fn settings_ui_system(
                             );
                             ui.checkbox(&mut game_config.click_to_add, "Click to Add");
                             ui.checkbox(&mut game_config.eye_blink_enabled, "Eye Blink");
+                            ui.checkbox(&mut game_config.hover_highlight, "Hover Glow");
+                            ui.checkbox(&mut game_config.hover_tooltip, "Tooltip");
+                            ui.checkbox(&mut game_config.instant_delete, "Instant Delete");
                             ui.separator();
                             ui.label("Shiny");
                             ui.checkbox(&mut shiny_config.enabled, "Enabled");
@@ -299,6 +499,71 @@ pub fn settings_ui_system(
                             ui.add(
                                 egui::Slider::new(&mut border_config.down, 0.0..=0.5).text("Down"),
                             );
+
+                            // Portal edges need the peer link (native only)
+                            #[cfg(not(target_arch = "wasm32"))]
+                            {
+                                ui.separator();
+                                ui.label("Portal");
+                                ui.horizontal(|ui| {
+                                    ui.checkbox(&mut border_config.portal_left, "Left");
+                                    ui.checkbox(&mut border_config.portal_right, "Right");
+                                });
+                                ui.horizontal(|ui| {
+                                    ui.checkbox(&mut border_config.portal_up, "Up");
+                                    ui.checkbox(&mut border_config.portal_down, "Down");
+                                });
+                            }
+                        });
+
+                    egui::CollapsingHeader::new("Gamepad")
+                        .default_open(false)
+                        .show(ui, |ui| {
+                            ui.checkbox(&mut gamepad_config.enabled, "Enabled");
+                            ui.add(
+                                egui::Slider::new(&mut gamepad_config.cursor_speed, 200.0..=2000.0)
+                                    .text("Cursor Speed"),
+                            );
+                            ui.checkbox(&mut gamepad_config.tilt_enabled, "Tilt Gravity");
+                            ui.add(
+                                egui::Slider::new(
+                                    &mut gamepad_config.max_tilt,
+                                    0.0..=std::f32::consts::PI,
+                                )
+                                .text("Max Tilt"),
+                            );
+                            ui.checkbox(&mut gamepad_config.rumble_enabled, "Rumble");
+                            ui.add(
+                                egui::Slider::new(&mut gamepad_config.rumble_threshold, 0.0..=50.0)
+                                    .text("Rumble Speed"),
+                            );
+                        });
+
+                    egui::CollapsingHeader::new("Controls")
+                        .default_open(false)
+                        .show(ui, |ui| {
+                            egui::Grid::new("key_bindings").show(ui, |ui| {
+                                for action in KeyAction::ALL {
+                                    ui.label(action.label());
+                                    let text = if key_bindings.listening == Some(action) {
+                                        "Press a key…".to_string()
+                                    } else {
+                                        key_bindings.chord(action).label()
+                                    };
+                                    if ui.button(text).clicked() {
+                                        key_bindings.listening = Some(action);
+                                        key_bindings.conflict = None;
+                                    }
+                                    ui.end_row();
+                                }
+                            });
+                            if let Some(other) = key_bindings.conflict {
+                                ui.label(format!("Already used by {}", other.label()));
+                            }
+                            if ui.button("Reset to Defaults").clicked() {
+                                *key_bindings = KeyBindings::default();
+                                key_bindings.save();
+                            }
                         });
                 });
         });
@@ -306,6 +571,589 @@ pub fn settings_ui_system(
     Ok(())
 }
 
+/// Speaki state the inspector shows and edits
+type InspectedSpeaki = (
+    &'static SpeakiName,
+    &'static Transform,
+    &'static mut SpeakiSize,
+    &'static mut Sprite,
+    &'static mut Velocity,
+    &'static mut RotationState,
+    &'static mut SpriteState,
+    Option<&'static mut Shiny>,
+    &'static mut IdleVoiceTimer,
+    &'static CurrentAudio,
+);
+
+/// Inspector panel: view and edit one speaki's own state
+pub fn inspector_ui_system(
+    mut commands: Commands,
+    mut contexts: EguiContexts,
+    mut inspector: ResMut<Inspector>,
+    mut speaki_query: Query<InspectedSpeaki, With<Speaki>>,
+    (sprites, shiny_config, merge_config): (Res<SpriteAssets>, Res<ShinyConfig>, Res<MergeConfig>),
+    mut audio_instances: ResMut<Assets<AudioInstance>>,
+    time: Res<Time>,
+) -> Result {
+    if !inspector.active && inspector.target.is_none() {
+        return Ok(());
+    }
+
+    let ctx = contexts.ctx_mut()?;
+
+    // Speaki is gone (deleted, merged, sent through a portal)
+    let inspected = inspector
+        .target
+        .and_then(|e| speaki_query.get_mut(e).ok().map(|item| (e, item)));
+    if inspected.is_none() {
+        inspector.target = None;
+    }
+
+    let mut open = true;
+    let mut inspect_mode = inspector.active;
+
+    egui::Window::new("Inspector")
+        .open(&mut open)
+        .default_width(240.0)
+        .resizable(false)
+        .show(ctx, |ui| {
+            ui.checkbox(&mut inspect_mode, "Inspect Mode (click a speaki)");
+
+            let Some((
+                target,
+                (
+                    name,
+                    transform,
+                    mut size,
+                    mut sprite,
+                    mut vel,
+                    mut rot,
+                    mut sprite_state,
+                    shiny,
+                    mut idle_timer,
+                    current_audio,
+                ),
+            )) = inspected
+            else {
+                return;
+            };
+
+            ui.separator();
+            ui.heading(&name.0);
+            let pos = transform.translation;
+            ui.label(format!("Position ({:.0}, {:.0})", pos.x, pos.y));
+
+            let mut new_size = size.0;
+            let slider = egui::Slider::new(&mut new_size, 50.0..=merge_config.max_size);
+            if ui.add(slider.text("Size")).changed() {
+                size.0 = new_size;
+                sprite.custom_size = Some(Vec2::splat(new_size));
+            }
+
+            ui.horizontal(|ui| {
+                ui.label("Velocity");
+                ui.add(egui::DragValue::new(&mut vel.x).speed(0.1).prefix("x "));
+                ui.add(egui::DragValue::new(&mut vel.y).speed(0.1).prefix("y "));
+            });
+            ui.add(egui::Slider::new(&mut rot.speed, -1.0..=1.0).text("Spin"));
+
+            // Copy so the sprite only updates when the index actually changes
+            if let Some(max_index) = sprites.states.len().checked_sub(1) {
+                let mut index = sprite_state.current_index;
+                if ui
+                    .add(egui::Slider::new(&mut index, 0..=max_index).text("Sprite"))
+                    .changed()
+                {
+                    sprite_state.current_index = index;
+                }
+            }
+
+            ui.separator();
+            let mut is_shiny = shiny.is_some();
+            if ui.checkbox(&mut is_shiny, "Shiny").changed() {
+                if is_shiny {
+                    let [r, g, b] = shiny_config.glow_color;
+                    commands.entity(target).insert(Shiny {
+                        base_color: Color::srgb(r, g, b),
+                        ..default()
+                    });
+                } else {
+                    commands.entity(target).remove::<Shiny>();
+                }
+            }
+
+            if let Some(mut shiny) = shiny {
+                let base = shiny.base_color.to_srgba();
+                let mut rgb = [base.red, base.green, base.blue];
+                ui.horizontal(|ui| {
+                    ui.label("Color");
+                    if ui.color_edit_button_rgb(&mut rgb).changed() {
+                        shiny.base_color = Color::srgb(rgb[0], rgb[1], rgb[2]);
+                    }
+                });
+                ui.add(
+                    egui::Slider::new(&mut shiny.pulse_phase, 0.0..=std::f32::consts::TAU)
+                        .text("Pulse Phase"),
+                );
+                ui.add(
+                    egui::DragValue::new(&mut shiny.next_explosion)
+                        .speed(0.1)
+                        .range(0.0..=60.0)
+                        .prefix("Next explosion ")
+                        .suffix("s"),
+                );
+            }
+
+            ui.separator();
+            ui.label(format!(
+                "Last idle voice {:.1}s ago",
+                time.elapsed_secs() - idle_timer.last_idle_time
+            ));
+            ui.add(
+                egui::Slider::new(&mut idle_timer.idle_cooldown, 0.0..=1.0).text("Idle Cooldown"),
+            );
+
+            match current_audio
+                .handle
+                .as_ref()
+                .and_then(|handle| audio_instances.get_mut(handle))
+            {
+                Some(instance) => {
+                    let voice = current_audio
+                        .voice_index
+                        .map_or("?".to_string(), |idx| idx.to_string());
+                    ui.label(format!("Voice #{} {:?}", voice, instance.state()));
+                    if ui.button("Stop").clicked() {
+                        instance.stop(AudioTween::default());
+                    }
+                }
+                None => {
+                    ui.label("Not speaking");
+                }
+            }
+        });
+
+    inspector.active = inspect_mode && open;
+    if !open {
+        inspector.target = None;
+    }
+
+    Ok(())
+}
+
+/// Voice mapping editor: pick which clips play for each category and how often
+pub fn voice_editor_ui_system(
+    mut contexts: EguiContexts,
+    mut voice_editor: ResMut<VoiceEditor>,
+    mut voice_groups: ResMut<VoiceGroups>,
+    audio_config: Res<AudioConfig>,
+    mut voice_events: MessageWriter<PlayVoiceEvent>,
+) -> Result {
+    if !voice_editor.open {
+        return Ok(());
+    }
+
+    let ctx = contexts.ctx_mut()?;
+
+    let mut open = true;
+    let mut changed = false;
+
+    egui::Window::new("Voice Mapping")
+        .open(&mut open)
+        .default_width(260.0)
+        .resizable(false)
+        .show(ctx, |ui| {
+            egui::ComboBox::from_label("Category")
+                .selected_text(voice_editor.category.label())
+                .show_ui(ui, |ui| {
+                    for category in VoiceCategory::ALL {
+                        ui.selectable_value(&mut voice_editor.category, category, category.label());
+                    }
+                });
+            ui.separator();
+
+            let category = voice_editor.category;
+            egui::ScrollArea::vertical()
+                .max_height(360.0)
+                .show(ui, |ui| {
+                    egui::Grid::new("voice_mapping").show(ui, |ui| {
+                        for voice in 0..voice_groups.files.len() {
+                            let weight = voice_groups.weight(category, voice);
+                            let mut assigned = weight.is_some();
+                            if ui
+                                .checkbox(&mut assigned, &voice_groups.names[voice])
+                                .changed()
+                            {
+                                voice_groups.set_weight(category, voice, assigned.then_some(1.0));
+                                changed = true;
+                            }
+
+                            // Weight is relative to the other clips in the category
+                            let mut new_weight = weight.unwrap_or(1.0);
+                            let drag = egui::DragValue::new(&mut new_weight)
+                                .speed(0.05)
+                                .range(0.0..=10.0)
+                                .prefix("× ");
+                            if ui.add_enabled(assigned, drag).changed() {
+                                voice_groups.set_weight(category, voice, Some(new_weight));
+                                changed = true;
+                            }
+
+                            if ui.button("Play").clicked() {
+                                voice_events.write(PlayVoiceEvent {
+                                    entity: None,
+                                    voice_index: voice,
+                                    volume: audio_config.idle_volume,
+                                    kind: VoiceKind::Manual,
+                                });
+                            }
+                            ui.end_row();
+                        }
+                    });
+                });
+
+            ui.separator();
+            ui.horizontal(|ui| {
+                if ui.button("Play Random").clicked()
+                    && let Some(idx) = voice_groups.pick(category)
+                {
+                    voice_events.write(PlayVoiceEvent {
+                        entity: None,
+                        voice_index: idx,
+                        volume: audio_config.idle_volume,
+                        kind: VoiceKind::Manual,
+                    });
+                }
+                if ui.button("Reset to Defaults").clicked() {
+                    *voice_groups = VoiceGroups::default();
+                    changed = true;
+                }
+            });
+        });
+
+    if changed {
+        voice_groups.save();
+    }
+    voice_editor.open = open;
+
+    Ok(())
+}
+
+/// Small indicator while the simulation is paused or not at normal speed
+pub fn simulation_hud_ui_system(
+    mut contexts: EguiContexts,
+    sim_speed: Res<SimulationSpeed>,
+) -> Result {
+    if !sim_speed.paused && sim_speed.speed == 1.0 {
+        return Ok(());
+    }
+
+    let ctx = contexts.ctx_mut()?;
+
+    let text = if sim_speed.paused {
+        "Paused".to_string()
+    } else {
+        format!("{}x", sim_speed.speed)
+    };
+
+    egui::Area::new(egui::Id::new("simulation_hud"))
+        .anchor(egui::Align2::CENTER_TOP, egui::vec2(0.0, 10.0))
+        .interactable(false)
+        .show(ctx, |ui| {
+            egui::Frame::popup(ui.style()).show(ui, |ui| {
+                ui.strong(text);
+            });
+        });
+
+    Ok(())
+}
+
+/// Hand cursor over speakis and an optional tooltip for the hovered one
+pub fn hover_feedback_ui_system(
+    mut commands: Commands,
+    mut contexts: EguiContexts,
+    window: Single<(Entity, Option<&CursorIcon>), With<PrimaryWindow>>,
+    game_config: Res<GameConfig>,
+    drag_state: Res<DragState>,
+    inspector: Res<Inspector>,
+    hovered_query: Query<(&Hovered, &SpeakiName, &SpeakiSize, Has<Shiny>), With<Speaki>>,
+) -> Result {
+    let ctx = contexts.ctx_mut()?;
+
+    // Let egui windows show their own cursor
+    if ctx.is_pointer_over_area() {
+        return Ok(());
+    }
+
+    let hovered = hovered_query.iter().find(|(h, ..)| h.get());
+    let busy = inspector.active || drag_state.dragged_entity.is_some();
+    let icon = CursorIcon::System(if inspector.active {
+        SystemCursorIcon::Crosshair
+    } else if drag_state.dragged_entity.is_some() {
+        SystemCursorIcon::Grabbing
+    } else if hovered.is_some() {
+        SystemCursorIcon::Grab
+    } else {
+        SystemCursorIcon::Default
+    });
+
+    // egui only sets the window cursor when its own icon changes, so put ours
+    // back whenever it differs
+    let (window, current_icon) = *window;
+    if current_icon != Some(&icon) {
+        commands.entity(window).insert(icon);
+    }
+
+    let Some((_, name, size, shiny)) = hovered.filter(|_| !busy) else {
+        return Ok(());
+    };
+
+    if !game_config.hover_tooltip {
+        return Ok(());
+    }
+
+    let Some(pointer_pos) = ctx.pointer_hover_pos() else {
+        return Ok(());
+    };
+
+    egui::Area::new(egui::Id::new("speaki_tooltip"))
+        .fixed_pos(pointer_pos + egui::vec2(16.0, 16.0))
+        .order(egui::Order::Tooltip)
+        .interactable(false)
+        .show(ctx, |ui| {
+            egui::Frame::popup(ui.style()).show(ui, |ui| {
+                ui.strong(&name.0);
+                ui.label(format!("{} ({:.0}px)", size.tier(), size.0));
+                if shiny {
+                    ui.label("Shiny");
+                }
+            });
+        });
+
+    Ok(())
+}
+
+/// Action picked from a speaki's context menu
+enum MenuAction {
+    Inspect,
+    Duplicate,
+    TogglePin,
+    ToggleShiny,
+    PlayVoice(usize),
+    Remove,
+    RemoveAllLikeThis,
+}
+
+/// Speaki state the context menu shows and edits
+type MenuTarget = (
+    Entity,
+    &'static Transform,
+    &'static mut SpeakiName,
+    &'static mut SpeakiSize,
+    &'static mut Sprite,
+    &'static mut Velocity,
+    Option<&'static Shiny>,
+    Has<Pinned>,
+);
+
+/// What the context menu's buttons do
+#[derive(SystemParam)]
+pub struct MenuActions<'w, 's> {
+    commands: Commands<'w, 's>,
+    inspector: ResMut<'w, Inspector>,
+    sprites: Res<'w, SpriteAssets>,
+    shiny_config: Res<'w, ShinyConfig>,
+    despawn_events: MessageWriter<'w, DespawnSpeakiEvent>,
+    voices: Voices<'w>,
+}
+
+/// Right-click menu: remove, inspect, duplicate, rename, pin, shiny, resize, play voice
+pub fn context_menu_ui_system(
+    mut contexts: EguiContexts,
+    mut menu: ResMut<ContextMenu>,
+    mut speaki_query: Query<MenuTarget, With<Speaki>>,
+    merge_config: Res<MergeConfig>,
+    actions: MenuActions,
+) -> Result {
+    let MenuActions {
+        mut commands,
+        mut inspector,
+        sprites,
+        shiny_config,
+        mut despawn_events,
+        mut voices,
+    } = actions;
+
+    let Some(target) = menu.target else {
+        return Ok(());
+    };
+    let just_opened = menu.is_changed();
+
+    let ctx = contexts.ctx_mut()?;
+
+    // Speaki is gone (deleted, merged, sent through a portal)
+    let Ok((_, transform, mut name, mut size, mut sprite, mut vel, shiny, pinned)) =
+        speaki_query.get_mut(target)
+    else {
+        menu.target = None;
+        return Ok(());
+    };
+
+    let mut action = None;
+
+    let response = egui::Area::new(egui::Id::new("speaki_context_menu"))
+        .fixed_pos(egui::pos2(menu.position.x, menu.position.y))
+        .order(egui::Order::Foreground)
+        .show(ctx, |ui| {
+            egui::Frame::menu(ui.style()).show(ui, |ui| {
+                ui.set_min_width(180.0);
+
+                ui.horizontal(|ui| {
+                    ui.label("Name");
+                    ui.text_edit_singleline(&mut name.0);
+                });
+
+                let mut new_size = size.0;
+                let slider = egui::Slider::new(&mut new_size, 50.0..=merge_config.max_size);
+                if ui.add(slider.text("Size")).changed() {
+                    size.0 = new_size;
+                    sprite.custom_size = Some(Vec2::splat(new_size));
+                }
+
+                ui.separator();
+
+                if ui.button("Inspect").clicked() {
+                    action = Some(MenuAction::Inspect);
+                }
+                if ui.button("Duplicate").clicked() {
+                    action = Some(MenuAction::Duplicate);
+                }
+                if ui.button(if pinned { "Unpin" } else { "Pin" }).clicked() {
+                    action = Some(MenuAction::TogglePin);
+                }
+                let shiny_label = if shiny.is_some() {
+                    "Make Normal"
+                } else {
+                    "Make Shiny"
+                };
+                if ui.button(shiny_label).clicked() {
+                    action = Some(MenuAction::ToggleShiny);
+                }
+
+                ui.menu_button("Play Voice", |ui| {
+                    for category in VoiceCategory::ALL {
+                        if !voices.groups.clips(category).is_empty()
+                            && ui.button(category.label()).clicked()
+                            && let Some(idx) = voices.groups.pick(category)
+                        {
+                            action = Some(MenuAction::PlayVoice(idx));
+                        }
+                    }
+                });
+
+                ui.separator();
+
+                if ui.button("Remove").clicked() {
+                    action = Some(MenuAction::Remove);
+                }
+                if ui.button("Remove All Like This").clicked() {
+                    action = Some(MenuAction::RemoveAllLikeThis);
+                }
+            });
+        })
+        .response;
+
+    // Close on Escape or a click outside the menu (not the right-click that opened it)
+    let clicked_outside = !just_opened
+        && ctx.input(|i| i.pointer.any_pressed())
+        && !response.contains_pointer()
+        && !ctx.is_pointer_over_area();
+    if clicked_outside || ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+        menu.target = None;
+    }
+
+    let Some(action) = action else {
+        return Ok(());
+    };
+    menu.target = None;
+
+    match action {
+        MenuAction::Inspect => {
+            inspector.target = Some(target);
+        }
+        MenuAction::Duplicate => {
+            let position = transform.translation.truncate() + Vec2::new(size.0 * 0.5, 0.0);
+            let entity = spawn_speaki_with(
+                &mut commands,
+                position,
+                Vec2::new(rand::random::<f32>() - 0.5, 2.0),
+                size.0,
+                shiny.map(|s| s.base_color),
+                &sprites,
+                &shiny_config,
+            );
+            commands.entity(entity).insert(SpeakiName(name.0.clone()));
+        }
+        MenuAction::TogglePin => {
+            // Start from rest either way
+            vel.x = 0.0;
+            vel.y = 0.0;
+            if pinned {
+                commands.entity(target).remove::<Pinned>();
+            } else {
+                commands.entity(target).insert(Pinned);
+            }
+        }
+        MenuAction::ToggleShiny => {
+            if shiny.is_some() {
+                commands.entity(target).remove::<Shiny>();
+            } else {
+                let [r, g, b] = shiny_config.glow_color;
+                commands.entity(target).insert(Shiny {
+                    base_color: Color::srgb(r, g, b),
+                    ..default()
+                });
+            }
+        }
+        MenuAction::PlayVoice(voice_index) => {
+            voices.events.write(PlayVoiceEvent {
+                entity: Some(target),
+                voice_index,
+                volume: voices.config.idle_volume,
+                kind: VoiceKind::Manual,
+            });
+        }
+        MenuAction::Remove => {
+            despawn_events.write(DespawnSpeakiEvent { entity: target });
+            play_remove_voice(target, &mut voices);
+        }
+        MenuAction::RemoveAllLikeThis => {
+            // Same size tier and same shininess
+            let tier = size.tier();
+            let is_shiny = shiny.is_some();
+            for (entity, _, _, other_size, .., other_shiny, _) in speaki_query.iter() {
+                if other_size.tier() == tier && other_shiny.is_some() == is_shiny {
+                    despawn_events.write(DespawnSpeakiEvent { entity });
+                }
+            }
+            play_remove_voice(target, &mut voices);
+        }
+    }
+
+    Ok(())
+}
+
+fn play_remove_voice(entity: Entity, voices: &mut Voices) {
+    if let Some(idx) = voices.groups.pick(VoiceCategory::Remove) {
+        voices.events.write(PlayVoiceEvent {
+            entity: Some(entity),
+            voice_index: idx,
+            volume: voices.config.remove_volume,
+            kind: VoiceKind::Remove,
+        });
+    }
+}
+
 /// Sync background color from config to ClearColor
 pub fn sync_background_color_system(
     game_config: Res<GameConfig>,
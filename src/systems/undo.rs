@@ -0,0 +1,290 @@
+use std::collections::{HashMap, HashSet};
+
+use bevy::prelude::*;
+
+use crate::components::*;
+use crate::events::*;
+use crate::resources::*;
+use crate::systems::spawn_speaki_with;
+
+/// Everything a snapshot keeps of a speaki
+type SnapshotSource = (
+    Entity,
+    Option<&'static UndoId>,
+    &'static Transform,
+    &'static Velocity,
+    &'static RotationState,
+    &'static SpriteState,
+    &'static SpeakiSize,
+    &'static SpeakiName,
+    Option<&'static Shiny>,
+    Has<Pinned>,
+);
+
+/// Speakis whose snapshot is out of date
+type SnapshotChanged = (
+    With<Speaki>,
+    Or<(
+        Changed<Transform>,
+        Changed<SpeakiSize>,
+        Changed<SpriteState>,
+        Changed<SpeakiName>,
+        Changed<Shiny>,
+        Added<Pinned>,
+    )>,
+);
+
+/// Record spawns, removals and merges made this frame as one undo step
+pub fn undo_record_system(
+    mut commands: Commands,
+    changed_query: Query<SnapshotSource, SnapshotChanged>,
+    added_query: Query<Entity, Added<Speaki>>,
+    (mut removed_speakis, mut unpinned): (RemovedComponents<Speaki>, RemovedComponents<Pinned>),
+    (mut despawn_events, mut merge_events): (
+        MessageReader<DespawnSpeakiEvent>,
+        MessageReader<MergeSpeakiEvent>,
+    ),
+    mut history: ResMut<UndoHistory>,
+    (mut snapshots, mut started): (Local<HashMap<Entity, SpeakiSnapshot>>, Local<bool>),
+) {
+    // Removed speakis are gone by now, so use their last recorded state
+    let mut entry = UndoEntry::default();
+    let mut removed_ids = HashSet::new();
+    let mut remove = |entity: Entity, entry: &mut UndoEntry| {
+        if let Some(snapshot) = snapshots.get(&entity)
+            && removed_ids.insert(snapshot.id)
+        {
+            entry.removed.push(snapshot.clone());
+        }
+    };
+
+    for event in despawn_events.read() {
+        remove(event.entity, &mut entry);
+    }
+
+    // A merge replaces both speakis with the grown survivor
+    let merges: Vec<_> = merge_events
+        .read()
+        .map(|e| (e.entity1, e.entity2))
+        .collect();
+    for &(entity1, entity2) in &merges {
+        remove(entity1, &mut entry);
+        remove(entity2, &mut entry);
+    }
+
+    // Only speakis that changed since last frame need a new snapshot
+    for entity in removed_speakis.read() {
+        snapshots.remove(&entity);
+    }
+    let unpinned: HashSet<Entity> = unpinned.read().collect();
+    for (entity, id, transform, vel, rot, sprite_state, size, name, shiny, pinned) in
+        changed_query.iter()
+    {
+        let id = match id {
+            Some(id) => id.0,
+            None => {
+                let id = history.next_id;
+                history.next_id += 1;
+                commands.entity(entity).insert(UndoId(id));
+                id
+            }
+        };
+
+        snapshots.insert(
+            entity,
+            SpeakiSnapshot {
+                id,
+                name: name.0.clone(),
+                transform: *transform,
+                velocity: Vec2::new(vel.x, vel.y),
+                rotation_speed: rot.speed,
+                sprite_index: sprite_state.current_index,
+                size: size.0,
+                shiny: shiny.cloned(),
+                pinned,
+            },
+        );
+    }
+    for entity in unpinned {
+        if let Some(snapshot) = snapshots.get_mut(&entity) {
+            snapshot.pinned = false;
+        }
+    }
+
+    for &(entity1, _) in &merges {
+        if let Some(snapshot) = snapshots.get(&entity1) {
+            entry.added.push(snapshot.clone());
+        }
+    }
+
+    for entity in added_query.iter() {
+        if let Some(snapshot) = snapshots.get(&entity) {
+            entry.added.push(snapshot.clone());
+        }
+    }
+
+    // The initial speakis and undo/redo itself are not undoable
+    let replaying = std::mem::take(&mut history.replaying);
+    if *started && !replaying && !(entry.removed.is_empty() && entry.added.is_empty()) {
+        history.push(entry);
+    }
+    *started = true;
+}
+
+/// Undo and redo the last spawn, removal or merge
+pub fn undo_keyboard_system(
+    mut commands: Commands,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    key_bindings: Res<KeyBindings>,
+    query: Query<(Entity, &UndoId, Has<Dragged>)>,
+    mut history: ResMut<UndoHistory>,
+    sprites: Res<SpriteAssets>,
+    shiny_config: Res<ShinyConfig>,
+) {
+    let undo = key_bindings.just_pressed(KeyAction::Undo, &keyboard);
+    let to_remove = if undo {
+        history.undo.last().map(|entry| &entry.added)
+    } else if key_bindings.just_pressed(KeyAction::Redo, &keyboard) {
+        history.redo.last().map(|entry| &entry.removed)
+    } else {
+        return;
+    };
+
+    let Some(to_remove) = to_remove else {
+        return;
+    };
+
+    let removed_ids: HashSet<u64> = to_remove.iter().map(|s| s.id).collect();
+    let removed: Vec<(Entity, bool)> = query
+        .iter()
+        .filter(|(_, id, _)| removed_ids.contains(&id.0))
+        .map(|(entity, _, held)| (entity, held))
+        .collect();
+
+    // Drag and touch state still point at held speakis, so wait until they are let go
+    if removed.iter().any(|&(_, held)| held) {
+        return;
+    }
+
+    let step = if undo {
+        history.undo.pop().map(|entry| {
+            history.redo.push(entry.clone());
+            entry.inverted()
+        })
+    } else {
+        history.redo.pop().inspect(|entry| {
+            history.undo.push(entry.clone());
+        })
+    };
+
+    let Some(step) = step else {
+        return;
+    };
+
+    for (entity, _) in removed {
+        commands.entity(entity).despawn();
+    }
+
+    for snapshot in &step.added {
+        restore_speaki(&mut commands, snapshot, &sprites, &shiny_config);
+    }
+
+    history.replaying = true;
+}
+
+/// Spawn a speaki back from a snapshot
+fn restore_speaki(
+    commands: &mut Commands,
+    snapshot: &SpeakiSnapshot,
+    sprites: &SpriteAssets,
+    shiny_config: &ShinyConfig,
+) {
+    let entity = spawn_speaki_with(
+        commands,
+        snapshot.transform.translation.truncate(),
+        snapshot.velocity,
+        snapshot.size,
+        snapshot.shiny.as_ref().map(|s| s.base_color),
+        sprites,
+        shiny_config,
+    );
+
+    let mut entity_commands = commands.entity(entity);
+    entity_commands.insert((
+        UndoId(snapshot.id),
+        SpeakiName(snapshot.name.clone()),
+        snapshot.transform,
+        RotationState {
+            speed: snapshot.rotation_speed,
+        },
+        SpriteState {
+            current_index: snapshot.sprite_index,
+        },
+    ));
+
+    // Keep the shiny's pulse and explosion timing
+    if let Some(shiny) = &snapshot.shiny {
+        entity_commands.insert(shiny.clone());
+    }
+    if snapshot.pinned {
+        entity_commands.insert(Pinned);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spawn(world: &mut World, size: f32) -> Entity {
+        world
+            .spawn((
+                Speaki,
+                Transform::default(),
+                Velocity::default(),
+                RotationState { speed: 0.0 },
+                SpriteState { current_index: 0 },
+                SpeakiSize(size),
+                SpeakiName(String::new()),
+            ))
+            .id()
+    }
+
+    #[test]
+    fn merge_is_one_step_with_the_grown_survivor() {
+        let mut world = World::new();
+        world.init_resource::<UndoHistory>();
+        world.init_resource::<Messages<DespawnSpeakiEvent>>();
+        world.init_resource::<Messages<MergeSpeakiEvent>>();
+        let record = world.register_system(undo_record_system);
+
+        // The initial speakis are not undoable
+        let survivor = spawn(&mut world, 100.0);
+        let merged = spawn(&mut world, 100.0);
+        world.run_system(record).unwrap();
+        assert!(world.resource::<UndoHistory>().undo.is_empty());
+        let survivor_id = world.get::<UndoId>(survivor).unwrap().0;
+        let merged_id = world.get::<UndoId>(merged).unwrap().0;
+
+        // Same as speaki_merge_system: one despawns, the other grows
+        world.write_message(MergeSpeakiEvent {
+            entity1: survivor,
+            entity2: merged,
+        });
+        world.despawn(merged);
+        world.get_mut::<SpeakiSize>(survivor).unwrap().0 = 150.0;
+        world.run_system(record).unwrap();
+
+        let history = world.resource::<UndoHistory>();
+        assert_eq!(history.undo.len(), 1);
+        let entry = &history.undo[0];
+
+        let mut removed: Vec<(u64, f32)> = entry.removed.iter().map(|s| (s.id, s.size)).collect();
+        removed.sort_by_key(|&(id, _)| id);
+        let mut expected = vec![(survivor_id, 100.0), (merged_id, 100.0)];
+        expected.sort_by_key(|&(id, _)| id);
+        assert_eq!(removed, expected);
+
+        let added: Vec<(u64, f32)> = entry.added.iter().map(|s| (s.id, s.size)).collect();
+        assert_eq!(added, [(survivor_id, 150.0)]);
+    }
+}